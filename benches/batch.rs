@@ -0,0 +1,90 @@
+//! Throughput of the batch conversion against converting one position at a time. The dhat
+//! allocator would slow every allocation down, so the heap allocations each makes are counted
+//! in a separate run with the `dhat-heap` feature instead of the timing:
+//!
+//! cargo bench --bench batch
+//! cargo bench --bench batch --profile dhat --features dhat-heap
+//! cargo flamegraph --bench batch --profile flamegraph
+
+use blue_telemetry_tui::batch::{to_geo_batch, EcefSlices, GeoSlicesMut};
+use blue_telemetry_tui::coord::{EcefCoord, Ellipsoid, GeoCoord};
+use std::hint::black_box;
+#[cfg(not(feature = "dhat-heap"))]
+use std::time::Instant;
+
+#[cfg(feature = "dhat-heap")]
+#[global_allocator]
+static ALLOC: dhat::Alloc = dhat::Alloc;
+
+const POINTS: usize = 1_000_000;
+
+// Time `convert`
+#[cfg(not(feature = "dhat-heap"))]
+fn measure(name: &str, mut convert: impl FnMut()) {
+    let start = Instant::now();
+    convert();
+    let elapsed = start.elapsed();
+    println!(
+        "{name:<16} {:>8.1} M points/s",
+        POINTS as f64 / elapsed.as_secs_f64() / 1e6
+    );
+}
+
+// Count the heap allocations `convert` makes
+#[cfg(feature = "dhat-heap")]
+fn measure(name: &str, mut convert: impl FnMut()) {
+    let before = dhat::HeapStats::get().total_blocks;
+    convert();
+    let allocations = dhat::HeapStats::get().total_blocks - before;
+    println!("{name:<16} {allocations:>8} allocations");
+}
+
+fn main() {
+    #[cfg(feature = "dhat-heap")]
+    let _profiler = dhat::Profiler::new_heap();
+    let ellipsoid = Ellipsoid::WGS84;
+
+    // A spread of latitudes and altitudes from the surface out to GEO
+    let points: Vec<EcefCoord> = (0..POINTS)
+        .map(|i| {
+            GeoCoord {
+                lat: (i % 180) as f64 - 89.5,
+                lon: (i % 360) as f64 - 180.0,
+                alt: (i % 1_000) as f64 * 36_000.0,
+            }
+            .to_ecef_on(&ellipsoid)
+        })
+        .collect();
+    let x: Vec<f64> = points.iter().map(|p| p.x).collect();
+    let y: Vec<f64> = points.iter().map(|p| p.y).collect();
+    let z: Vec<f64> = points.iter().map(|p| p.z).collect();
+    let (mut lat, mut lon, mut alt) = (vec![0.0; POINTS], vec![0.0; POINTS], vec![0.0; POINTS]);
+    let mut batch = || {
+        to_geo_batch(
+            EcefSlices {
+                x: &x,
+                y: &y,
+                z: &z,
+            },
+            GeoSlicesMut {
+                lat: &mut lat,
+                lon: &mut lon,
+                alt: &mut alt,
+            },
+            &ellipsoid,
+        )
+        .unwrap();
+    };
+    // Starts the rayon thread pool, when enabled, outside the measurement
+    batch();
+
+    measure("to_geo", || {
+        let geo: Vec<GeoCoord> = points.iter().map(|p| p.to_geo().unwrap()).collect();
+        black_box(geo);
+    });
+    measure("to_geo_olson", || {
+        let geo: Vec<GeoCoord> = points.iter().map(|p| p.to_geo_olson().unwrap()).collect();
+        black_box(geo);
+    });
+    measure("to_geo_batch", batch);
+}
@@ -0,0 +1,37 @@
+use blue_telemetry_tui::coord::{Ellipsoid, GeoCoord};
+use blue_telemetry_tui::geodetic::{accuracy_sweep, ALGORITHMS};
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+fn geodetic(c: &mut Criterion) {
+    let ellipsoid = Ellipsoid::WGS84;
+    for conversion in ALGORITHMS {
+        println!("{}", accuracy_sweep(conversion, &ellipsoid));
+    }
+
+    // A spread of points around the New Shepard flight profile
+    let points: Vec<_> = (0..100)
+        .map(|i| {
+            GeoCoord {
+                lat: 31.4 + f64::from(i) * 0.001,
+                lon: -104.8,
+                alt: f64::from(i) * 1_100.0,
+            }
+            .to_ecef_on(&ellipsoid)
+        })
+        .collect();
+
+    let mut group = c.benchmark_group("ecef_to_geo");
+    for conversion in ALGORITHMS {
+        group.bench_function(conversion.name(), |b| {
+            b.iter(|| {
+                for point in &points {
+                    black_box(conversion.to_geo(black_box(point), &ellipsoid).unwrap());
+                }
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, geodetic);
+criterion_main!(benches);
@@ -0,0 +1,282 @@
+use blue_telemetry_tui::coord::{EcefCoord, Ellipsoid, GeoCoord};
+
+use crate::csv_reader::TelemetryRecord;
+use crate::events::{air_density, FlightEvent, FlightPhase};
+use crate::gravity::acceleration;
+
+// Integration step of the propagator in seconds
+const STEP_SECONDS: f64 = 0.1;
+// Give up on trajectories that have not come down after an hour
+const MAX_SECONDS: f64 = 3_600.0;
+
+/// Where a ballistic trajectory from the state at `from_ns` peaks and comes down
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Prediction {
+    pub from_ns: u64,
+    pub apogee_ns: u64,
+    pub apogee_alt: f64,
+    /// Time and place the trajectory comes back down to the ground altitude
+    pub impact_ns: Option<u64>,
+    pub impact: Option<GeoCoord>,
+}
+
+/// Propagate the state of `record` with J2 gravity in the rotating ECEF frame until it
+/// comes back down to `ground_alt`, a height above `ellipsoid` like the predicted apogee.
+/// Without a ballistic coefficient (mass over drag coefficient times area, in kg/m^2)
+/// the trajectory is drag free.
+#[allow(clippy::cast_possible_truncation)]
+#[allow(clippy::cast_sign_loss)]
+pub fn predict(
+    record: &TelemetryRecord,
+    ground_alt: f64,
+    ballistic_coefficient: Option<f64>,
+    ellipsoid: &Ellipsoid,
+) -> Prediction {
+    let derivative = |state: &[f64; 6]| -> [f64; 6] {
+        let pos = [state[0], state[1], state[2]];
+        let vel = [state[3], state[4], state[5]];
+        let mut accel = acceleration(&pos, &vel);
+        if let Some(beta) = ballistic_coefficient {
+            // The atmosphere turns with the Earth, so the ECEF velocity is the airspeed
+            let alt = altitude(state, ellipsoid);
+            let speed = vel.iter().map(|v| v * v).sum::<f64>().sqrt();
+            let drag = 0.5 * air_density(alt) * speed / beta;
+            for i in 0..3 {
+                accel[i] -= drag * vel[i];
+            }
+        }
+        [vel[0], vel[1], vel[2], accel[0], accel[1], accel[2]]
+    };
+
+    let mut state = [
+        record.pos_x,
+        record.pos_y,
+        record.pos_z,
+        record.vel_x,
+        record.vel_y,
+        record.vel_z,
+    ];
+    let mut alt = altitude(&state, ellipsoid);
+    let mut t = 0.0;
+    let mut prediction = Prediction {
+        from_ns: record.timestamp_ns,
+        apogee_ns: record.timestamp_ns,
+        apogee_alt: alt,
+        impact_ns: None,
+        impact: None,
+    };
+    let offset_ns = |t: f64| record.timestamp_ns + (t * 1e9).round() as u64;
+
+    while t < MAX_SECONDS {
+        let next = rk4_step(&state, STEP_SECONDS, &derivative);
+        let next_alt = altitude(&next, ellipsoid);
+        t += STEP_SECONDS;
+
+        if next_alt > prediction.apogee_alt {
+            prediction.apogee_alt = next_alt;
+            prediction.apogee_ns = offset_ns(t);
+        }
+
+        if next_alt < ground_alt && next_alt < alt {
+            // Interpolate to where the step crossed the ground
+            let fraction = (alt - ground_alt) / (alt - next_alt);
+            let crossing: [f64; 6] =
+                std::array::from_fn(|i| state[i] + fraction * (next[i] - state[i]));
+            prediction.impact_ns = Some(offset_ns(t - STEP_SECONDS * (1.0 - fraction)));
+            prediction.impact = ecef(&crossing).to_geo_on(ellipsoid).ok();
+            break;
+        }
+
+        state = next;
+        alt = next_alt;
+    }
+
+    prediction
+}
+
+/// Predictions from the records of the coast phase, at most one every `interval_ns`
+pub fn predict_coast(
+    records: &[TelemetryRecord],
+    events: &[FlightEvent],
+    interval_ns: u64,
+    ground_alt: f64,
+    ballistic_coefficient: Option<f64>,
+    ellipsoid: &Ellipsoid,
+) -> Vec<Prediction> {
+    let mut predictions: Vec<Prediction> = Vec::new();
+
+    for record in records {
+        if FlightPhase::at(events, record.timestamp_ns) != FlightPhase::Coast
+            || !record.flags.is_empty()
+        {
+            continue;
+        }
+        if predictions
+            .last()
+            .is_some_and(|last| record.timestamp_ns < last.from_ns + interval_ns)
+        {
+            continue;
+        }
+        predictions.push(predict(
+            record,
+            ground_alt,
+            ballistic_coefficient,
+            ellipsoid,
+        ));
+    }
+
+    predictions
+}
+
+fn rk4_step(state: &[f64; 6], dt: f64, derivative: &impl Fn(&[f64; 6]) -> [f64; 6]) -> [f64; 6] {
+    let offset =
+        |k: &[f64; 6], scale: f64| -> [f64; 6] { std::array::from_fn(|i| state[i] + scale * k[i]) };
+    let k1 = derivative(state);
+    let k2 = derivative(&offset(&k1, 0.5 * dt));
+    let k3 = derivative(&offset(&k2, 0.5 * dt));
+    let k4 = derivative(&offset(&k3, dt));
+
+    std::array::from_fn(|i| state[i] + dt / 6.0 * (k1[i] + 2.0 * k2[i] + 2.0 * k3[i] + k4[i]))
+}
+
+fn ecef(state: &[f64; 6]) -> EcefCoord {
+    EcefCoord {
+        x: state[0],
+        y: state[1],
+        z: state[2],
+    }
+}
+
+fn altitude(state: &[f64; 6], ellipsoid: &Ellipsoid) -> f64 {
+    ecef(state)
+        .to_geo_on(ellipsoid)
+        .map_or(f64::NAN, |geo| geo.alt)
+}
+
+/***************************
+           Tests
+***************************/
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::EventKind;
+    use crate::gravity::{EARTH_RATE, GM, J2};
+    use blue_telemetry_tui::coord::Ellipsoid;
+    use float_cmp::approx_eq;
+
+    const A: f64 = Ellipsoid::WGS84.a;
+
+    // Straight up from the equator at 1 km/s
+    fn launch() -> TelemetryRecord {
+        TelemetryRecord {
+            pos_x: A,
+            vel_x: 1_000.0,
+            ..TelemetryRecord::default()
+        }
+    }
+
+    // Radius where the launch runs out of energy, from the energy integral of the rotating
+    // frame with the J2 potential on the equator. Ignores the little sideways Coriolis velocity
+    fn apogee_radius() -> f64 {
+        let potential =
+            |r: f64| -GM / r * (1.0 + 0.5 * J2 * (A / r).powi(2)) - 0.5 * (EARTH_RATE * r).powi(2);
+        let energy = 0.5 * 1_000.0f64.powi(2) + potential(A);
+        let (mut low, mut high) = (A, 2.0 * A);
+        for _ in 0..100 {
+            let mid = 0.5 * (low + high);
+            if potential(mid) < energy {
+                low = mid;
+            } else {
+                high = mid;
+            }
+        }
+        low
+    }
+
+    #[test]
+    fn drag_free_apogee_and_impact() {
+        let prediction = predict(&launch(), 0.0, None, &Ellipsoid::WGS84);
+
+        assert!(approx_eq!(
+            f64,
+            apogee_radius() - A,
+            prediction.apogee_alt,
+            epsilon = 10.0
+        ));
+        // A little over v / g to the top as gravity weakens with height, and the same back down
+        assert!(approx_eq!(
+            f64,
+            103.3,
+            prediction.apogee_ns as f64 / 1e9,
+            epsilon = 0.2
+        ));
+        let impact_s = prediction.impact_ns.unwrap() as f64 / 1e9;
+        assert!(approx_eq!(f64, 206.7, impact_s, epsilon = 0.2));
+        // The Earth turns east underneath, so it comes down a little to the west
+        let impact = prediction.impact.unwrap();
+        assert!(approx_eq!(f64, 0.0, impact.lat, epsilon = 1e-9));
+        assert!(impact.lon < 0.0 && impact.lon > -0.05);
+        assert!(approx_eq!(f64, 0.0, impact.alt, epsilon = 1e-3));
+    }
+
+    #[test]
+    fn heights_above_the_chosen_ellipsoid() {
+        let sphere = Ellipsoid::new(6_371_000.0, 0.0).unwrap();
+
+        let on_wgs84 = predict(&launch(), 0.0, None, &Ellipsoid::WGS84);
+        let on_sphere = predict(&launch(), 0.0, None, &sphere);
+
+        // The launch stays on the equator, where the sphere is 7 km further down
+        assert!(approx_eq!(
+            f64,
+            on_wgs84.apogee_alt + A - sphere.a,
+            on_sphere.apogee_alt,
+            epsilon = 1e-3
+        ));
+        // Coming down to the sphere takes longer and the impact is measured from it
+        assert!(on_sphere.impact_ns.unwrap() > on_wgs84.impact_ns.unwrap());
+        assert!(approx_eq!(
+            f64,
+            0.0,
+            on_sphere.impact.unwrap().alt,
+            epsilon = 1e-3
+        ));
+    }
+
+    #[test]
+    fn drag_lowers_apogee() {
+        let drag_free = predict(&launch(), 0.0, None, &Ellipsoid::WGS84);
+        let with_drag = predict(&launch(), 0.0, Some(1_000.0), &Ellipsoid::WGS84);
+
+        assert!(with_drag.apogee_alt < drag_free.apogee_alt - 1_000.0);
+        assert!(with_drag.impact_ns.unwrap() < drag_free.impact_ns.unwrap());
+    }
+
+    #[test]
+    fn only_coast_is_predicted() {
+        let records: Vec<TelemetryRecord> = (0..10u64)
+            .map(|i| TelemetryRecord {
+                timestamp_ns: i * 500_000_000,
+                ..launch()
+            })
+            .collect();
+        let event = |kind, index: usize| FlightEvent {
+            kind,
+            index,
+            timestamp_ns: records[index].timestamp_ns,
+        };
+        let events = vec![event(EventKind::Meco, 2), event(EventKind::Apogee, 8)];
+
+        let predictions = predict_coast(
+            &records,
+            &events,
+            1_000_000_000,
+            0.0,
+            None,
+            &Ellipsoid::WGS84,
+        );
+
+        let from: Vec<u64> = predictions.iter().map(|p| p.from_ns).collect();
+        assert_eq!(vec![1_000_000_000, 2_000_000_000, 3_000_000_000], from);
+    }
+}
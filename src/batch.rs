@@ -0,0 +1,302 @@
+//! ECEF to geodetic conversion of many positions at once. Coordinates are kept in
+//! separate arrays so the loop streams through contiguous memory, and the results are
+//! written into buffers the caller owns so nothing is allocated per call. The loop body is
+//! straight-line arithmetic without branches or early returns, so it can be vectorised
+//! wherever the target has vector versions of `sqrt` and `atan2`.
+
+use std::error::Error;
+use std::fmt;
+
+use crate::coord::{Ellipsoid, MIN_RADIUS};
+
+// Halves of the input larger than this are converted on separate rayon threads
+#[cfg(feature = "rayon")]
+const PARALLEL_CHUNK: usize = 16_384;
+
+/// ECEF positions in meters, one array per axis
+#[derive(Debug, Clone, Copy)]
+pub struct EcefSlices<'a> {
+    pub x: &'a [f64],
+    pub y: &'a [f64],
+    pub z: &'a [f64],
+}
+
+/// Output buffers for the geodetic latitude and longitude in degrees and altitude in meters
+#[derive(Debug)]
+pub struct GeoSlicesMut<'a> {
+    pub lat: &'a mut [f64],
+    pub lon: &'a mut [f64],
+    pub alt: &'a mut [f64],
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct LengthMismatch {
+    pub expected: usize,
+    pub actual: usize,
+}
+
+impl fmt::Display for LengthMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Batch arrays differ in length, expected {} but got {}",
+            self.expected, self.actual
+        )
+    }
+}
+
+impl Error for LengthMismatch {}
+
+impl<'a> EcefSlices<'a> {
+    #[cfg(feature = "rayon")]
+    fn split_at(self, mid: usize) -> (Self, Self) {
+        let (x_left, x_right) = self.x.split_at(mid);
+        let (y_left, y_right) = self.y.split_at(mid);
+        let (z_left, z_right) = self.z.split_at(mid);
+        (
+            EcefSlices {
+                x: x_left,
+                y: y_left,
+                z: z_left,
+            },
+            EcefSlices {
+                x: x_right,
+                y: y_right,
+                z: z_right,
+            },
+        )
+    }
+}
+
+impl<'a> GeoSlicesMut<'a> {
+    #[cfg(feature = "rayon")]
+    fn split_at(self, mid: usize) -> (Self, Self) {
+        let (lat_left, lat_right) = self.lat.split_at_mut(mid);
+        let (lon_left, lon_right) = self.lon.split_at_mut(mid);
+        let (alt_left, alt_right) = self.alt.split_at_mut(mid);
+        (
+            GeoSlicesMut {
+                lat: lat_left,
+                lon: lon_left,
+                alt: alt_left,
+            },
+            GeoSlicesMut {
+                lat: lat_right,
+                lon: lon_right,
+                alt: alt_right,
+            },
+        )
+    }
+}
+
+/// Convert every position with Olson's algorithm into `output`, which must be as long as the
+/// input. Positions without geodetic coordinates (see `EcefCoord::check`) come out as NaN.
+/// With the `rayon` feature large batches are split across threads.
+pub fn to_geo_batch(
+    input: EcefSlices,
+    output: GeoSlicesMut,
+    ellipsoid: &Ellipsoid,
+) -> Result<(), LengthMismatch> {
+    let expected = input.x.len();
+    let lengths = [
+        input.y.len(),
+        input.z.len(),
+        output.lat.len(),
+        output.lon.len(),
+        output.alt.len(),
+    ];
+    if let Some(actual) = lengths.into_iter().find(|len| *len != expected) {
+        return Err(LengthMismatch { expected, actual });
+    }
+
+    convert(input, output, ellipsoid);
+    Ok(())
+}
+
+fn convert(input: EcefSlices, output: GeoSlicesMut, ellipsoid: &Ellipsoid) {
+    #[cfg(feature = "rayon")]
+    if input.x.len() > PARALLEL_CHUNK {
+        let mid = input.x.len() / 2;
+        let (input_left, input_right) = input.split_at(mid);
+        let (output_left, output_right) = output.split_at(mid);
+        rayon::join(
+            || convert(input_left, output_left, ellipsoid),
+            || convert(input_right, output_right, ellipsoid),
+        );
+        return;
+    }
+
+    // Olson's constants, see `EcefCoord::to_geo_olson_on`
+    let e2 = ellipsoid.e_sq();
+    let a = ellipsoid.a;
+    let a1 = a * e2;
+    let a2 = a1 * a1;
+    let a3 = a1 * e2 / 2.0;
+    let a4 = 2.5 * a2;
+    let a5 = a1 + a3;
+    let a6 = 1.0 - e2;
+
+    let positions = input.x.iter().zip(input.y).zip(input.z);
+    let geo = output
+        .lat
+        .iter_mut()
+        .zip(output.lon.iter_mut())
+        .zip(output.alt.iter_mut());
+    for (((&x, &y), &z), ((lat, lon), alt)) in positions.zip(geo) {
+        let zp = z.abs();
+        let w2 = x * x + y * y;
+        let w = w2.sqrt();
+        let z2 = z * z;
+        let r2 = w2 + z2;
+        let r = r2.sqrt();
+        let s2 = z2 / r2;
+        let c2 = w2 / r2;
+        let u = a2 / r;
+        let v = a3 - a4 / r;
+
+        // First guess at the sine of the latitude towards the equator and its cosine towards
+        // the poles, whichever is better conditioned, with the other from the identity
+        let near_equator = c2 > 0.4;
+        let guess = if near_equator {
+            (zp / r) * (1.0 + c2 * (a1 + u + s2 * v) / r)
+        } else {
+            (w / r) * (1.0 - s2 * (a5 - u - c2 * v) / r)
+        };
+        let other = (1.0 - guess * guess).sqrt();
+        let (s, c) = if near_equator {
+            (guess, other)
+        } else {
+            (other, guess)
+        };
+
+        // One Newton step on the latitude, which also gives the height
+        let g = 1.0 - e2 * s * s;
+        let rg = a / g.sqrt();
+        let rf = a6 * rg;
+        let u = w - rg * c;
+        let v = zp - rf * s;
+        let f = c * u + s * v;
+        let m = c * v - s * u;
+        let p = m / (rf / g + f);
+        let sign = if z < 0.0 { -1.0 } else { 1.0 };
+
+        // The same positions `EcefCoord::check` rejects come out as NaN
+        let valid = x.is_finite() && y.is_finite() && z.is_finite() && r >= MIN_RADIUS;
+        let mask = |value: f64| if valid { value } else { f64::NAN };
+        *lat = mask(sign * (s.atan2(c) + p).to_degrees());
+        *lon = mask(y.atan2(x).to_degrees());
+        *alt = mask(f + m * p / 2.0);
+    }
+}
+
+/***************************
+           Tests
+***************************/
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::coord::{EcefCoord, GeoCoord};
+    use float_cmp::approx_eq;
+
+    // The Earth's center followed by points from 1000 km underground out past GEO
+    fn positions(count: usize) -> (Vec<f64>, Vec<f64>, Vec<f64>) {
+        let center = EcefCoord {
+            x: 0.0,
+            y: 0.0,
+            z: 0.0,
+        };
+        let ecef: Vec<EcefCoord> = std::iter::once(center)
+            .chain((1..count).map(|i| {
+                GeoCoord {
+                    lat: (i % 181) as f64 - 90.0,
+                    lon: (i % 360) as f64 - 180.0,
+                    alt: i as f64 * 37_000_000.0 / count as f64 - 1_000_000.0,
+                }
+                .to_ecef_on(&Ellipsoid::WGS84)
+            }))
+            .collect();
+        (
+            ecef.iter().map(|p| p.x).collect(),
+            ecef.iter().map(|p| p.y).collect(),
+            ecef.iter().map(|p| p.z).collect(),
+        )
+    }
+
+    fn matches_per_point(count: usize) {
+        let (mut x, y, mut z) = positions(count);
+        x[1] = f64::NAN;
+        z[2] = f64::INFINITY;
+        let (mut lat, mut lon, mut alt) = (vec![0.0; count], vec![0.0; count], vec![0.0; count]);
+
+        to_geo_batch(
+            EcefSlices {
+                x: &x,
+                y: &y,
+                z: &z,
+            },
+            GeoSlicesMut {
+                lat: &mut lat,
+                lon: &mut lon,
+                alt: &mut alt,
+            },
+            &Ellipsoid::WGS84,
+        )
+        .unwrap();
+
+        for i in 0..count {
+            let ecef = EcefCoord {
+                x: x[i],
+                y: y[i],
+                z: z[i],
+            };
+            match ecef.to_geo() {
+                Ok(expected) => {
+                    assert!(approx_eq!(f64, expected.lat, lat[i], epsilon = 1e-9));
+                    assert!(approx_eq!(f64, expected.lon, lon[i], epsilon = 1e-9));
+                    assert!(approx_eq!(f64, expected.alt, alt[i], epsilon = 1e-6));
+                }
+                Err(_) => assert!(lat[i].is_nan() && lon[i].is_nan() && alt[i].is_nan()),
+            }
+        }
+        assert!(alt[0].is_nan() && lat[1].is_nan() && lon[2].is_nan());
+    }
+
+    #[test]
+    fn batch_matches_per_point() {
+        matches_per_point(1_000);
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn parallel_batch_matches_per_point() {
+        matches_per_point(100_000);
+    }
+
+    #[test]
+    fn length_mismatch() {
+        let (x, y, z) = positions(10);
+        let (mut lat, mut lon, mut alt) = (vec![0.0; 10], vec![0.0; 9], vec![0.0; 10]);
+
+        let actual = to_geo_batch(
+            EcefSlices {
+                x: &x,
+                y: &y,
+                z: &z,
+            },
+            GeoSlicesMut {
+                lat: &mut lat,
+                lon: &mut lon,
+                alt: &mut alt,
+            },
+            &Ellipsoid::WGS84,
+        );
+
+        assert_eq!(
+            Err(LengthMismatch {
+                expected: 10,
+                actual: 9
+            }),
+            actual
+        );
+    }
+}
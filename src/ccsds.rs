@@ -0,0 +1,515 @@
+use csv::ReaderBuilder;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::fs::File;
+use std::io::{ErrorKind, Read};
+
+use crate::csv_reader::TelemetryRecord;
+use crate::input::open_input;
+
+// Size of the CCSDS space packet primary header in bytes
+const PRIMARY_HEADER_LEN: usize = 6;
+// Sequence counts are 14 bits wide and wrap around
+const SEQ_COUNT_MODULO: u16 = 1 << 14;
+
+/// The fixed 6 byte header at the start of every CCSDS space packet.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct PrimaryHeader {
+    pub version: u8,
+    pub packet_type: u8,
+    pub secondary_header: bool,
+    pub apid: u16,
+    pub sequence_flags: u8,
+    pub sequence_count: u16,
+    /// Number of bytes in the packet data field minus one
+    pub data_length: u16,
+}
+
+impl PrimaryHeader {
+    pub fn parse(bytes: &[u8; PRIMARY_HEADER_LEN]) -> Self {
+        let word_1 = u16::from_be_bytes([bytes[0], bytes[1]]);
+        let word_2 = u16::from_be_bytes([bytes[2], bytes[3]]);
+        let word_3 = u16::from_be_bytes([bytes[4], bytes[5]]);
+
+        PrimaryHeader {
+            version: (word_1 >> 13) as u8,
+            packet_type: ((word_1 >> 12) & 0x1) as u8,
+            secondary_header: (word_1 >> 11) & 0x1 == 1,
+            apid: word_1 & 0x07FF,
+            sequence_flags: (word_2 >> 14) as u8,
+            sequence_count: word_2 & 0x3FFF,
+            data_length: word_3,
+        }
+    }
+}
+
+/// The `TelemetryRecord` field a packet parameter is decoded into.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum TelemetryField {
+    Timestamp,
+    PosX,
+    PosY,
+    PosZ,
+    VelX,
+    VelY,
+    VelZ,
+    /// Component of the attitude quaternion, `0..4`
+    Quat(usize),
+    /// Packet error control word, checked rather than stored
+    Crc,
+}
+
+impl TelemetryField {
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "timestamp_ns" => Some(TelemetryField::Timestamp),
+            "pos_x" => Some(TelemetryField::PosX),
+            "pos_y" => Some(TelemetryField::PosY),
+            "pos_z" => Some(TelemetryField::PosZ),
+            "vel_x" => Some(TelemetryField::VelX),
+            "vel_y" => Some(TelemetryField::VelY),
+            "vel_z" => Some(TelemetryField::VelZ),
+            "quat_1" => Some(TelemetryField::Quat(0)),
+            "quat_2" => Some(TelemetryField::Quat(1)),
+            "quat_3" => Some(TelemetryField::Quat(2)),
+            "quat_4" => Some(TelemetryField::Quat(3)),
+            "crc" => Some(TelemetryField::Crc),
+            _ => None,
+        }
+    }
+}
+
+/// Big endian encodings a packet parameter can be stored as.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum FieldType {
+    U16,
+    U32,
+    U64,
+    I32,
+    I64,
+    F32,
+    F64,
+}
+
+impl FieldType {
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "u16" => Some(FieldType::U16),
+            "u32" => Some(FieldType::U32),
+            "u64" => Some(FieldType::U64),
+            "i32" => Some(FieldType::I32),
+            "i64" => Some(FieldType::I64),
+            "f32" => Some(FieldType::F32),
+            "f64" => Some(FieldType::F64),
+            _ => None,
+        }
+    }
+
+    fn size(self) -> usize {
+        match self {
+            FieldType::U16 => 2,
+            FieldType::U32 | FieldType::I32 | FieldType::F32 => 4,
+            FieldType::U64 | FieldType::I64 | FieldType::F64 => 8,
+        }
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    fn read(self, bytes: &[u8]) -> f64 {
+        match self {
+            FieldType::U16 => f64::from(u16::from_be_bytes(bytes.try_into().unwrap())),
+            FieldType::U32 => f64::from(u32::from_be_bytes(bytes.try_into().unwrap())),
+            FieldType::U64 => u64::from_be_bytes(bytes.try_into().unwrap()) as f64,
+            FieldType::I32 => f64::from(i32::from_be_bytes(bytes.try_into().unwrap())),
+            FieldType::I64 => i64::from_be_bytes(bytes.try_into().unwrap()) as f64,
+            FieldType::F32 => f64::from(f32::from_be_bytes(bytes.try_into().unwrap())),
+            FieldType::F64 => f64::from_be_bytes(bytes.try_into().unwrap()),
+        }
+    }
+
+    /// Read a count such as a timestamp. Integers are read exactly rather than through f64,
+    /// which can't hold every integer above 2^53. Floats are rounded and negatives become 0.
+    #[allow(clippy::cast_possible_truncation)]
+    #[allow(clippy::cast_sign_loss)]
+    fn read_u64(self, bytes: &[u8]) -> u64 {
+        match self {
+            FieldType::U16 => u64::from(u16::from_be_bytes(bytes.try_into().unwrap())),
+            FieldType::U32 => u64::from(u32::from_be_bytes(bytes.try_into().unwrap())),
+            FieldType::U64 => u64::from_be_bytes(bytes.try_into().unwrap()),
+            FieldType::I32 => {
+                u64::try_from(i32::from_be_bytes(bytes.try_into().unwrap())).unwrap_or(0)
+            }
+            FieldType::I64 => {
+                u64::try_from(i64::from_be_bytes(bytes.try_into().unwrap())).unwrap_or(0)
+            }
+            FieldType::F32 | FieldType::F64 => self.read(bytes).round() as u64,
+        }
+    }
+}
+
+/// Location of a single parameter inside the packet data field.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct FieldDefinition {
+    pub field: TelemetryField,
+    /// Byte offset from the start of the packet data field
+    pub offset: usize,
+    pub field_type: FieldType,
+}
+
+/// Maps APIDs to the layout of the telemetry carried in their packets.
+#[derive(Debug, Default)]
+pub struct PacketDefinitions {
+    apids: HashMap<u16, Vec<FieldDefinition>>,
+}
+
+#[derive(Debug)]
+pub struct DefinitionError(String);
+
+impl fmt::Display for DefinitionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid packet definition: {}", self.0)
+    }
+}
+
+impl Error for DefinitionError {}
+
+impl PacketDefinitions {
+    pub fn from_file(file_path: &str) -> Result<Self, Box<dyn Error>> {
+        Self::from_reader(File::open(file_path)?)
+    }
+
+    /// Read a definition csv with the columns `apid,field,offset,type`.
+    /// A `crc` field marks a CRC-16 that covers every byte of the packet before it.
+    pub fn from_reader<R: Read>(reader: R) -> Result<Self, Box<dyn Error>> {
+        let mut rdr = ReaderBuilder::new()
+            .has_headers(true)
+            .trim(csv::Trim::All)
+            .from_reader(reader);
+        let mut definitions = PacketDefinitions::default();
+
+        for result in rdr.records() {
+            let record = result?;
+            let apid = record.get(0).unwrap_or_default().parse::<u16>()?;
+            let field_name = record.get(1).unwrap_or_default();
+            let offset = record.get(2).unwrap_or_default().parse::<usize>()?;
+            let type_name = record.get(3).unwrap_or_default();
+
+            let field = TelemetryField::parse(field_name)
+                .ok_or_else(|| DefinitionError(format!("unknown field `{field_name}`")))?;
+            let field_type = FieldType::parse(type_name)
+                .ok_or_else(|| DefinitionError(format!("unknown type `{type_name}`")))?;
+            if field == TelemetryField::Crc && field_type != FieldType::U16 {
+                return Err(DefinitionError("crc fields must be u16".to_string()).into());
+            }
+            if offset.checked_add(field_type.size()).is_none() {
+                return Err(DefinitionError(format!("offset {offset} is too large")).into());
+            }
+
+            definitions
+                .apids
+                .entry(apid)
+                .or_default()
+                .push(FieldDefinition {
+                    field,
+                    offset,
+                    field_type,
+                });
+        }
+
+        Ok(definitions)
+    }
+
+    pub fn get(&self, apid: u16) -> Option<&[FieldDefinition]> {
+        self.apids.get(&apid).map(Vec::as_slice)
+    }
+}
+
+/// Link quality statistics gathered while decoding a packet stream.
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct DecodeReport {
+    pub packets: usize,
+    pub records: usize,
+    /// Number of discontinuities in the per APID sequence counters
+    pub sequence_gaps: usize,
+    /// Total packets lost according to the sequence counters
+    pub missing_packets: usize,
+    pub crc_errors: usize,
+    pub unknown_apids: usize,
+    /// Packets too short to contain every defined field
+    pub malformed_packets: usize,
+    /// The stream ended in the middle of a packet
+    pub truncated: bool,
+}
+
+/// CRC-16/CCITT-FALSE as recommended for the CCSDS packet error control field
+pub fn crc16(bytes: &[u8]) -> u16 {
+    let mut crc: u16 = 0xFFFF;
+    for byte in bytes {
+        crc ^= u16::from(*byte) << 8;
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 {
+                (crc << 1) ^ 0x1021
+            } else {
+                crc << 1
+            };
+        }
+    }
+    crc
+}
+
+pub fn read_ccsds(
+    file_path: &str,
+    definitions: &PacketDefinitions,
+) -> Result<(Vec<TelemetryRecord>, DecodeReport), Box<dyn Error>> {
+    decode_packets(open_input(file_path)?, definitions)
+}
+
+/// Decode every packet in the stream into records, skipping packets that fail their CRC.
+pub fn decode_packets<R: Read>(
+    mut reader: R,
+    definitions: &PacketDefinitions,
+) -> Result<(Vec<TelemetryRecord>, DecodeReport), Box<dyn Error>> {
+    let mut records: Vec<TelemetryRecord> = Vec::new();
+    let mut report = DecodeReport::default();
+    let mut last_sequence: HashMap<u16, u16> = HashMap::new();
+
+    loop {
+        let mut header_bytes = [0u8; PRIMARY_HEADER_LEN];
+        match read_full(&mut reader, &mut header_bytes)? {
+            0 => break,
+            PRIMARY_HEADER_LEN => {}
+            _ => {
+                report.truncated = true;
+                break;
+            }
+        }
+        let header = PrimaryHeader::parse(&header_bytes);
+
+        let mut data = vec![0u8; usize::from(header.data_length) + 1];
+        if read_full(&mut reader, &mut data)? != data.len() {
+            report.truncated = true;
+            break;
+        }
+        report.packets += 1;
+
+        // Idle packets only exist to fill the link
+        if header.apid == 0x07FF {
+            continue;
+        }
+
+        let Some(fields) = definitions.get(header.apid) else {
+            report.unknown_apids += 1;
+            continue;
+        };
+        if fields.iter().any(|def| {
+            def.offset
+                .checked_add(def.field_type.size())
+                .is_none_or(|end| end > data.len())
+        }) {
+            report.malformed_packets += 1;
+            continue;
+        }
+
+        let mut record = TelemetryRecord::default();
+        let mut crc_ok = true;
+        for def in fields {
+            let bytes = &data[def.offset..def.offset + def.field_type.size()];
+            match def.field {
+                TelemetryField::Crc => {
+                    let expected = u16::from_be_bytes([bytes[0], bytes[1]]);
+                    let mut covered = header_bytes.to_vec();
+                    covered.extend_from_slice(&data[..def.offset]);
+                    crc_ok &= crc16(&covered) == expected;
+                }
+                TelemetryField::Timestamp => {
+                    record.timestamp_ns = def.field_type.read_u64(bytes);
+                }
+                TelemetryField::PosX => record.pos_x = def.field_type.read(bytes),
+                TelemetryField::PosY => record.pos_y = def.field_type.read(bytes),
+                TelemetryField::PosZ => record.pos_z = def.field_type.read(bytes),
+                TelemetryField::VelX => record.vel_x = def.field_type.read(bytes),
+                TelemetryField::VelY => record.vel_y = def.field_type.read(bytes),
+                TelemetryField::VelZ => record.vel_z = def.field_type.read(bytes),
+                TelemetryField::Quat(i) => record.quat[i] = def.field_type.read(bytes),
+            }
+        }
+
+        if !crc_ok {
+            report.crc_errors += 1;
+            continue;
+        }
+
+        // Track sequence counter continuity per APID, once the counter can be trusted
+        if let Some(previous) = last_sequence.insert(header.apid, header.sequence_count) {
+            let expected = (previous + 1) % SEQ_COUNT_MODULO;
+            if header.sequence_count != expected {
+                report.sequence_gaps += 1;
+                report.missing_packets += usize::from(
+                    (header.sequence_count + SEQ_COUNT_MODULO - expected) % SEQ_COUNT_MODULO,
+                );
+            }
+        }
+        records.push(record);
+    }
+
+    report.records = records.len();
+    Ok((records, report))
+}
+
+// Like `read_exact`, but reports how many bytes were read before the end of the stream
+fn read_full<R: Read>(reader: &mut R, buf: &mut [u8]) -> Result<usize, std::io::Error> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(filled)
+}
+
+/***************************
+           Tests
+***************************/
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    const DEFINITION: &str = "apid,field,offset,type
+100,timestamp_ns,0,u64
+100,pos_x,8,f64
+100,pos_y,16,f64
+100,pos_z,24,f64
+100,vel_x,32,f32
+100,vel_y,36,f32
+100,vel_z,40,f32
+100,crc,44,u16
+";
+
+    fn packet(apid: u16, sequence_count: u16, timestamp_ns: u64, corrupt: bool) -> Vec<u8> {
+        let mut data = Vec::new();
+        data.extend_from_slice(&timestamp_ns.to_be_bytes());
+        data.extend_from_slice(&1.0f64.to_be_bytes());
+        data.extend_from_slice(&2.0f64.to_be_bytes());
+        data.extend_from_slice(&3.0f64.to_be_bytes());
+        data.extend_from_slice(&4.0f32.to_be_bytes());
+        data.extend_from_slice(&5.0f32.to_be_bytes());
+        data.extend_from_slice(&6.0f32.to_be_bytes());
+
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&(0x0800 | apid).to_be_bytes());
+        bytes.extend_from_slice(&(0xC000 | sequence_count).to_be_bytes());
+        bytes.extend_from_slice(&((data.len() + 2 - 1) as u16).to_be_bytes());
+        bytes.extend_from_slice(&data);
+        let crc = crc16(&bytes);
+        bytes.extend_from_slice(&crc.to_be_bytes());
+
+        if corrupt {
+            bytes[10] ^= 0xFF;
+        }
+        bytes
+    }
+
+    #[test]
+    fn primary_header_parse() {
+        let header = PrimaryHeader::parse(&[0x08, 0x64, 0xC0, 0x2A, 0x00, 0x2D]);
+        assert_eq!(
+            PrimaryHeader {
+                version: 0,
+                packet_type: 0,
+                secondary_header: true,
+                apid: 100,
+                sequence_flags: 3,
+                sequence_count: 42,
+                data_length: 45,
+            },
+            header
+        );
+    }
+
+    #[test]
+    fn crc16_check_value() {
+        // Standard check value for CRC-16/CCITT-FALSE
+        assert_eq!(0x29B1, crc16(b"123456789"));
+    }
+
+    #[test]
+    fn decode_packets_into_records() {
+        let definitions = PacketDefinitions::from_reader(DEFINITION.as_bytes()).unwrap();
+        // Above 2^53, where f64 can't hold every nanosecond
+        let mut stream = packet(100, 0, 1_602_601_200_123_456_789, false);
+        stream.extend(packet(100, 1, 2_000, false));
+
+        let (records, report) = decode_packets(stream.as_slice(), &definitions).unwrap();
+
+        assert_eq!(2, records.len());
+        assert_eq!(1_602_601_200_123_456_789, records[0].timestamp_ns);
+        assert_eq!(2_000, records[1].timestamp_ns);
+        assert_eq!(3.0, records[1].pos_z);
+        assert_eq!(6.0, records[1].vel_z);
+        assert_eq!(
+            DecodeReport {
+                packets: 2,
+                records: 2,
+                ..DecodeReport::default()
+            },
+            report
+        );
+    }
+
+    #[rstest]
+    #[case(vec![0, 1, 2, 3], 0, 0)]
+    #[case(vec![0, 1, 4, 5], 1, 2)]
+    #[case(vec![16382, 16383, 0, 1], 0, 0)]
+    #[case(vec![16383, 2], 1, 2)]
+    fn sequence_gaps(
+        #[case] counts: Vec<u16>,
+        #[case] expected_gaps: usize,
+        #[case] expected_missing: usize,
+    ) {
+        let definitions = PacketDefinitions::from_reader(DEFINITION.as_bytes()).unwrap();
+        let stream: Vec<u8> = counts
+            .iter()
+            .flat_map(|count| packet(100, *count, 0, false))
+            .collect();
+
+        let (_, report) = decode_packets(stream.as_slice(), &definitions).unwrap();
+
+        assert_eq!(expected_gaps, report.sequence_gaps);
+        assert_eq!(expected_missing, report.missing_packets);
+    }
+
+    #[test]
+    fn crc_errors_and_unknown_apids_are_skipped() {
+        let definitions = PacketDefinitions::from_reader(DEFINITION.as_bytes()).unwrap();
+        let mut stream = packet(100, 0, 500, false);
+        // A corrupted packet's counter isn't what the next one is checked against
+        stream.extend(packet(100, 9_000, 1_000, true));
+        stream.extend(packet(200, 0, 2_000, false));
+        stream.extend(packet(100, 1, 3_000, false));
+        // A corrupted packet that was in sequence still leaves a gap
+        stream.extend(packet(100, 2, 3_500, true));
+        stream.extend(packet(100, 3, 4_000, false));
+        // Cut the final packet short
+        stream.extend(&packet(100, 4, 5_000, false)[..20]);
+
+        let (records, report) = decode_packets(stream.as_slice(), &definitions).unwrap();
+
+        let timestamps: Vec<u64> = records.iter().map(|r| r.timestamp_ns).collect();
+        assert_eq!(vec![500, 3_000, 4_000], timestamps);
+        assert_eq!(2, report.crc_errors);
+        assert_eq!(1, report.unknown_apids);
+        assert_eq!((1, 1), (report.sequence_gaps, report.missing_packets));
+        assert!(report.truncated);
+    }
+
+    #[rstest]
+    #[case("apid,field,offset,type\n100,altitude,0,f64\n")]
+    #[case("apid,field,offset,type\n100,pos_x,18446744073709551615,f64\n")]
+    fn invalid_definition(#[case] definition: &str) {
+        assert!(PacketDefinitions::from_reader(definition.as_bytes()).is_err());
+    }
+}
@@ -0,0 +1,215 @@
+use std::error::Error;
+use std::fmt;
+
+use crate::csv_reader::TelemetryRecord;
+
+// Longer gaps are played as a single empty bin, so a stray timestamp days ahead doesn't
+// fill memory with empty ones
+const MAX_EMPTY_BINS: u64 = 1_000;
+
+/// How records are grouped into the chunks the UI steps through
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ChunkStrategy {
+    /// A new chunk starts once a record is more than `duration_ns` after the first
+    /// record of the current chunk, so boundaries drift with the data
+    Drift { duration_ns: u64 },
+    /// Bins aligned to multiples of `duration_ns` in absolute time. Gaps in the data give empty
+    /// bins, one for the whole gap past a thousand of them
+    AlignedBins { duration_ns: u64 },
+    /// Every chunk holds `size` records, the last one may hold fewer
+    Count { size: usize },
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct Chunk {
+    /// Start of the time the chunk covers, which may be before its first record
+    pub start_ns: u64,
+    pub records: Vec<TelemetryRecord>,
+}
+
+#[derive(Debug)]
+pub struct ChunkSizeError(ChunkStrategy);
+
+impl fmt::Display for ChunkSizeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "chunks can't be empty, got {:?}", self.0)
+    }
+}
+
+impl Error for ChunkSizeError {}
+
+/// Group the records into chunks, an error if the duration or size is zero
+pub fn chunk_records(
+    records: Vec<TelemetryRecord>,
+    strategy: ChunkStrategy,
+) -> Result<Vec<Chunk>, ChunkSizeError> {
+    match strategy {
+        ChunkStrategy::Drift { duration_ns: 0 }
+        | ChunkStrategy::AlignedBins { duration_ns: 0 }
+        | ChunkStrategy::Count { size: 0 } => Err(ChunkSizeError(strategy)),
+        ChunkStrategy::Drift { duration_ns } => Ok(drift_chunks(records, duration_ns)),
+        ChunkStrategy::AlignedBins { duration_ns } => Ok(aligned_bins(records, duration_ns)),
+        ChunkStrategy::Count { size } => Ok(count_chunks(records, size)),
+    }
+}
+
+fn drift_chunks(records: Vec<TelemetryRecord>, time_chunk_duration: u64) -> Vec<Chunk> {
+    let mut current_chunk: Vec<TelemetryRecord> = Vec::new();
+    let mut previous_timestamp: Option<u64> = None;
+    let mut chunks: Vec<Vec<TelemetryRecord>> = Vec::new();
+
+    for row in records {
+        let timestamp_ns = row.timestamp_ns;
+
+        // If it's the first row, start a new chunk
+        if previous_timestamp.is_none() {
+            current_chunk.push(row);
+            previous_timestamp = Some(timestamp_ns);
+            continue;
+        }
+
+        // Check the time difference from the previous timestamp
+        if let Some(prev_ts) = previous_timestamp {
+            let time_diff = timestamp_ns.saturating_sub(prev_ts);
+
+            // If time difference exceeds the chunk duration, start a new chunk
+            if time_diff > time_chunk_duration {
+                // push current chunk since we are done with it.
+                chunks.push(current_chunk);
+
+                // Create a new chunk to populate
+                current_chunk = Vec::new();
+                current_chunk.push(row);
+
+                // Update the previous timestamp
+                previous_timestamp = Some(timestamp_ns);
+            } else {
+                current_chunk.push(row); // Add the row to the current chunk
+            }
+        }
+    }
+
+    // Add the last chunk to the vec
+    if !current_chunk.is_empty() {
+        chunks.push(current_chunk);
+    }
+
+    chunks.into_iter().map(starting_at_first_record).collect()
+}
+
+fn aligned_bins(records: Vec<TelemetryRecord>, duration_ns: u64) -> Vec<Chunk> {
+    let mut chunks: Vec<Chunk> = Vec::new();
+
+    for row in records {
+        let bin_start = row.timestamp_ns / duration_ns * duration_ns;
+
+        // Add empty bins for any gap since the last record
+        match chunks.last().map(|chunk| chunk.start_ns) {
+            Some(start_ns) if start_ns >= bin_start => {}
+            previous => {
+                if let Some(start_ns) = previous {
+                    let empty_bins = match (bin_start - start_ns) / duration_ns - 1 {
+                        bins if bins > MAX_EMPTY_BINS => 1,
+                        bins => bins,
+                    };
+                    for bin in 1..=empty_bins {
+                        chunks.push(Chunk {
+                            start_ns: start_ns + bin * duration_ns,
+                            records: Vec::new(),
+                        });
+                    }
+                }
+                chunks.push(Chunk {
+                    start_ns: bin_start,
+                    records: Vec::new(),
+                });
+            }
+        }
+
+        chunks.last_mut().unwrap().records.push(row);
+    }
+
+    chunks
+}
+
+fn count_chunks(records: Vec<TelemetryRecord>, size: usize) -> Vec<Chunk> {
+    let mut chunks: Vec<Vec<TelemetryRecord>> = Vec::new();
+    let mut records = records.into_iter().peekable();
+
+    while records.peek().is_some() {
+        chunks.push(records.by_ref().take(size).collect());
+    }
+
+    chunks.into_iter().map(starting_at_first_record).collect()
+}
+
+fn starting_at_first_record(records: Vec<TelemetryRecord>) -> Chunk {
+    Chunk {
+        start_ns: records.first().map_or(0, |record| record.timestamp_ns),
+        records,
+    }
+}
+
+/***************************
+           Tests
+***************************/
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    fn records(timestamps: &[u64]) -> Vec<TelemetryRecord> {
+        timestamps
+            .iter()
+            .map(|timestamp_ns| TelemetryRecord {
+                timestamp_ns: *timestamp_ns,
+                ..TelemetryRecord::default()
+            })
+            .collect()
+    }
+
+    #[rstest]
+    #[case(ChunkStrategy::Drift { duration_ns: 10 }, vec![(5, 3), (16, 3), (50, 1)])]
+    #[case(ChunkStrategy::AlignedBins { duration_ns: 10 }, vec![(0, 2), (10, 3), (20, 1), (30, 0), (40, 0), (50, 1)])]
+    #[case(ChunkStrategy::Count { size: 4 }, vec![(5, 4), (19, 3)])]
+    fn chunk_strategies(#[case] strategy: ChunkStrategy, #[case] expected: Vec<(u64, usize)>) {
+        let records = records(&[5, 8, 12, 16, 19, 25, 50]);
+
+        let actual: Vec<(u64, usize)> = chunk_records(records, strategy)
+            .unwrap()
+            .iter()
+            .map(|chunk| (chunk.start_ns, chunk.records.len()))
+            .collect();
+
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn long_gap_is_one_bin() {
+        let strategy = ChunkStrategy::AlignedBins { duration_ns: 10 };
+        let chunks = chunk_records(records(&[5, 15, u64::MAX]), strategy).unwrap();
+
+        let actual: Vec<(u64, usize)> = chunks
+            .iter()
+            .map(|chunk| (chunk.start_ns, chunk.records.len()))
+            .collect();
+        assert_eq!(
+            vec![(0, 1), (10, 1), (20, 0), (u64::MAX / 10 * 10, 1)],
+            actual
+        );
+    }
+
+    #[test]
+    fn no_records_no_chunks() {
+        let strategy = ChunkStrategy::AlignedBins { duration_ns: 10 };
+        assert!(chunk_records(Vec::new(), strategy).unwrap().is_empty());
+    }
+
+    #[rstest]
+    #[case(ChunkStrategy::Drift { duration_ns: 0 })]
+    #[case(ChunkStrategy::AlignedBins { duration_ns: 0 })]
+    #[case(ChunkStrategy::Count { size: 0 })]
+    fn empty_chunks(#[case] strategy: ChunkStrategy) {
+        assert!(chunk_records(records(&[5, 8]), strategy).is_err());
+    }
+}
@@ -1,6 +1,128 @@
-// WGS84 parameters for EcEf to Geo conversions
-const A: f64 = 6_378_137.0; // semi-major axis in meters
-const E_SQ: f64 = 0.006_694_379_990_141_4; // eccentricity squared
+use clap::ValueEnum;
+use std::error::Error;
+use std::f64::consts::PI;
+use std::fmt;
+
+// Inside this radius the geodetic latitude is ill defined and the closed form solutions break down
+pub(crate) const MIN_RADIUS: f64 = 100_000.0;
+// The latitude iteration converges in a handful of steps anywhere outside MIN_RADIUS
+const MAX_ITERATIONS: usize = 20;
+// Vincenty's inverse converges slowly for nearly antipodal points and not at all for some
+const MAX_GEODESIC_ITERATIONS: usize = 200;
+
+/// Reference ellipsoid of a geodetic datum
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Ellipsoid {
+    /// Semi-major axis in meters
+    pub a: f64,
+    /// Flattening
+    pub f: f64,
+}
+
+impl Ellipsoid {
+    pub const WGS84: Ellipsoid = Ellipsoid {
+        a: 6_378_137.0,
+        f: 1.0 / 298.257_223_563,
+    };
+    pub const GRS80: Ellipsoid = Ellipsoid {
+        a: 6_378_137.0,
+        f: 1.0 / 298.257_222_101,
+    };
+
+    /// A user defined ellipsoid from its semi-major axis and inverse flattening, 0 for a sphere.
+    /// The axis has to be positive and the inverse flattening above 1 so the polar axis is too.
+    pub fn new(a: f64, inverse_flattening: f64) -> Result<Self, EllipsoidError> {
+        if !(a.is_finite() && a > 0.0) {
+            return Err(EllipsoidError(format!(
+                "semi-major axis {a} is not a positive length"
+            )));
+        }
+        let f = if inverse_flattening == 0.0 {
+            0.0
+        } else if inverse_flattening.is_finite() && inverse_flattening > 1.0 {
+            1.0 / inverse_flattening
+        } else {
+            return Err(EllipsoidError(format!(
+                "inverse flattening {inverse_flattening} is neither 0 nor above 1"
+            )));
+        };
+        Ok(Ellipsoid { a, f })
+    }
+
+    /// First eccentricity squared
+    pub fn e_sq(&self) -> f64 {
+        self.f * (2.0 - self.f)
+    }
+
+    /// Height above the ellipsoid of a point `p` from the polar axis and `z` above the equator
+    /// with geodetic latitude `lat` in radians. Unlike `p / cos(lat) - N` it holds at the poles
+    pub fn height(&self, p: f64, z: f64, lat: f64) -> f64 {
+        let (sin_lat, cos_lat) = lat.sin_cos();
+        p * cos_lat + z * sin_lat - self.a * (1.0 - self.e_sq() * sin_lat * sin_lat).sqrt()
+    }
+
+    /// Mean radius (2a + b) / 3, the sphere used for great-circle calculations
+    pub fn mean_radius(&self) -> f64 {
+        self.a * (3.0 - self.f) / 3.0
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct EllipsoidError(String);
+
+impl fmt::Display for EllipsoidError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid ellipsoid: {}", self.0)
+    }
+}
+
+impl Error for EllipsoidError {}
+
+impl Default for Ellipsoid {
+    fn default() -> Self {
+        Ellipsoid::WGS84
+    }
+}
+
+/// The ellipsoids that can be picked by name on the command line
+#[derive(ValueEnum, Debug, Default, PartialEq, Clone, Copy)]
+pub enum Datum {
+    #[default]
+    Wgs84,
+    Grs80,
+}
+
+impl Datum {
+    pub fn ellipsoid(self) -> Ellipsoid {
+        match self {
+            Datum::Wgs84 => Ellipsoid::WGS84,
+            Datum::Grs80 => Ellipsoid::GRS80,
+        }
+    }
+}
+
+/// Why an ECEF position has no geodetic coordinates
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum GeoError {
+    /// A coordinate is NaN or infinite
+    NonFinite,
+    /// Too close to the Earth's center, the distance from it in meters
+    NearCenter(f64),
+}
+
+impl fmt::Display for GeoError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GeoError::NonFinite => write!(f, "ECEF position is not finite"),
+            GeoError::NearCenter(radius) => write!(
+                f,
+                "ECEF position is only {radius:.0} m from the Earth's center"
+            ),
+        }
+    }
+}
+
+impl Error for GeoError {}
 
 #[derive(Debug)]
 pub struct EcefCoord {
@@ -11,14 +133,255 @@ pub struct EcefCoord {
 
 #[derive(Debug, PartialEq, Clone, Copy)]
 pub struct GeoCoord {
-    lat: f64,
-    lon: f64,
+    pub lat: f64,
+    pub lon: f64,
     pub alt: f64,
 }
 
+/// Shortest path between two points on the surface
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Geodesic {
+    /// In meters
+    pub distance: f64,
+    /// Bearings in degrees clockwise from north, leaving the start and arriving at the end
+    pub initial_azimuth: f64,
+    pub final_azimuth: f64,
+    /// Measured along a great circle on a sphere rather than on the ellipsoid
+    pub spherical: bool,
+}
+
+impl GeoCoord {
+    /// Rotate an ECEF vector (e.g. a velocity) into the local east, north, up frame
+    pub fn ecef_to_enu(&self, x: f64, y: f64, z: f64) -> [f64; 3] {
+        let (sin_lat, cos_lat) = self.lat.to_radians().sin_cos();
+        let (sin_lon, cos_lon) = self.lon.to_radians().sin_cos();
+
+        let east = -sin_lon * x + cos_lon * y;
+        let north = -sin_lat * cos_lon * x - sin_lat * sin_lon * y + cos_lat * z;
+        let up = cos_lat * cos_lon * x + cos_lat * sin_lon * y + sin_lat * z;
+        [east, north, up]
+    }
+
+    /// Back to ECEF, exact for any latitude and altitude
+    pub fn to_ecef_on(&self, ellipsoid: &Ellipsoid) -> EcefCoord {
+        let e_sq = ellipsoid.e_sq();
+        let (sin_lat, cos_lat) = self.lat.to_radians().sin_cos();
+        let (sin_lon, cos_lon) = self.lon.to_radians().sin_cos();
+        let n = ellipsoid.a / (1.0 - e_sq * sin_lat * sin_lat).sqrt();
+
+        EcefCoord {
+            x: (n + self.alt) * cos_lat * cos_lon,
+            y: (n + self.alt) * cos_lat * sin_lon,
+            z: (n * (1.0 - e_sq) + self.alt) * sin_lat,
+        }
+    }
+
+    /// Great-circle path to `to` on a sphere of `radius` meters, with the haversine formula
+    pub fn great_circle_inverse(&self, to: &GeoCoord, radius: f64) -> Geodesic {
+        let (lat1, lat2) = (self.lat.to_radians(), to.lat.to_radians());
+        let dlon = (to.lon - self.lon).to_radians();
+
+        let h = ((lat2 - lat1) / 2.0).sin().powi(2)
+            + lat1.cos() * lat2.cos() * (dlon / 2.0).sin().powi(2);
+        let distance = 2.0 * radius * h.sqrt().atan2((1.0 - h).sqrt());
+
+        let bearing = |lat1: f64, lat2: f64, dlon: f64| {
+            (dlon.sin() * lat2.cos())
+                .atan2(lat1.cos() * lat2.sin() - lat1.sin() * lat2.cos() * dlon.cos())
+        };
+        Geodesic {
+            distance,
+            initial_azimuth: azimuth(bearing(lat1, lat2, dlon)),
+            final_azimuth: azimuth(bearing(lat2, lat1, -dlon) + PI),
+            spherical: true,
+        }
+    }
+
+    /// The point `distance` meters along the great circle leaving at `azimuth` degrees
+    pub fn great_circle_direct(&self, azimuth: f64, distance: f64, radius: f64) -> GeoCoord {
+        let lat1 = self.lat.to_radians();
+        let bearing = azimuth.to_radians();
+        let delta = distance / radius;
+
+        let lat2 = (lat1.sin() * delta.cos() + lat1.cos() * delta.sin() * bearing.cos()).asin();
+        let dlon =
+            (bearing.sin() * delta.sin() * lat1.cos()).atan2(delta.cos() - lat1.sin() * lat2.sin());
+
+        GeoCoord {
+            lat: lat2.to_degrees(),
+            lon: normalize_lon(self.lon + dlon.to_degrees()),
+            alt: self.alt,
+        }
+    }
+
+    /// Geodesic to `to` on the ellipsoid with Vincenty's inverse formula, good to well under a
+    /// millimeter. The iteration does not converge for some nearly antipodal points, which get
+    /// the great circle on the mean sphere instead, marked `spherical` and within about 0.5%
+    pub fn geodesic_inverse(&self, to: &GeoCoord, ellipsoid: &Ellipsoid) -> Geodesic {
+        let (a, f) = (ellipsoid.a, ellipsoid.f);
+        let b = a * (1.0 - f);
+        let l = (to.lon - self.lon).to_radians();
+        let (sin_u1, cos_u1) = ((1.0 - f) * self.lat.to_radians().tan()).atan().sin_cos();
+        let (sin_u2, cos_u2) = ((1.0 - f) * to.lat.to_radians().tan()).atan().sin_cos();
+
+        let mut lambda = l;
+        for _ in 0..MAX_GEODESIC_ITERATIONS {
+            let (sin_lambda, cos_lambda) = lambda.sin_cos();
+            let sin_sigma =
+                (cos_u2 * sin_lambda).hypot(cos_u1 * sin_u2 - sin_u1 * cos_u2 * cos_lambda);
+            if sin_sigma == 0.0 {
+                // The same point
+                return Geodesic {
+                    distance: 0.0,
+                    initial_azimuth: 0.0,
+                    final_azimuth: 0.0,
+                    spherical: false,
+                };
+            }
+            let cos_sigma = sin_u1 * sin_u2 + cos_u1 * cos_u2 * cos_lambda;
+            let sigma = sin_sigma.atan2(cos_sigma);
+            let sin_alpha = cos_u1 * cos_u2 * sin_lambda / sin_sigma;
+            let cos_sq_alpha = 1.0 - sin_alpha * sin_alpha;
+            // On the equator cos^2 alpha is zero and the midpoint term drops out
+            let cos_2sigma_m = if cos_sq_alpha == 0.0 {
+                0.0
+            } else {
+                cos_sigma - 2.0 * sin_u1 * sin_u2 / cos_sq_alpha
+            };
+            let c = f / 16.0 * cos_sq_alpha * (4.0 + f * (4.0 - 3.0 * cos_sq_alpha));
+            let previous = lambda;
+            lambda = l
+                + (1.0 - c)
+                    * f
+                    * sin_alpha
+                    * (sigma
+                        + c * sin_sigma
+                            * (cos_2sigma_m
+                                + c * cos_sigma * (-1.0 + 2.0 * cos_2sigma_m * cos_2sigma_m)));
+
+            if (lambda - previous).abs() < 1e-12 {
+                let u_sq = cos_sq_alpha * (a * a - b * b) / (b * b);
+                let delta_sigma = delta_sigma(u_sq, sin_sigma, cos_sigma, cos_2sigma_m);
+                let (sin_lambda, cos_lambda) = lambda.sin_cos();
+                return Geodesic {
+                    distance: b * big_a(u_sq) * (sigma - delta_sigma),
+                    initial_azimuth: azimuth(
+                        (cos_u2 * sin_lambda).atan2(cos_u1 * sin_u2 - sin_u1 * cos_u2 * cos_lambda),
+                    ),
+                    final_azimuth: azimuth(
+                        (cos_u1 * sin_lambda)
+                            .atan2(-sin_u1 * cos_u2 + cos_u1 * sin_u2 * cos_lambda),
+                    ),
+                    spherical: false,
+                };
+            }
+        }
+        self.great_circle_inverse(to, ellipsoid.mean_radius())
+    }
+
+    /// The point `distance` meters along the geodesic leaving at `azimuth` degrees, with
+    /// Vincenty's direct formula
+    pub fn geodesic_direct(&self, azimuth: f64, distance: f64, ellipsoid: &Ellipsoid) -> GeoCoord {
+        let (a, f) = (ellipsoid.a, ellipsoid.f);
+        let b = a * (1.0 - f);
+        let (sin_alpha1, cos_alpha1) = azimuth.to_radians().sin_cos();
+        let tan_u1 = (1.0 - f) * self.lat.to_radians().tan();
+        let (sin_u1, cos_u1) = tan_u1.atan().sin_cos();
+
+        let sigma1 = tan_u1.atan2(cos_alpha1);
+        let sin_alpha = cos_u1 * sin_alpha1;
+        let cos_sq_alpha = 1.0 - sin_alpha * sin_alpha;
+        let u_sq = cos_sq_alpha * (a * a - b * b) / (b * b);
+        let scaled = distance / (b * big_a(u_sq));
+
+        let mut sigma = scaled;
+        let mut cos_2sigma_m = (2.0 * sigma1 + sigma).cos();
+        for _ in 0..MAX_GEODESIC_ITERATIONS {
+            cos_2sigma_m = (2.0 * sigma1 + sigma).cos();
+            let (sin_sigma, cos_sigma) = sigma.sin_cos();
+            let previous = sigma;
+            sigma = scaled + delta_sigma(u_sq, sin_sigma, cos_sigma, cos_2sigma_m);
+            if (sigma - previous).abs() < 1e-12 {
+                break;
+            }
+        }
+
+        let (sin_sigma, cos_sigma) = sigma.sin_cos();
+        let tmp = sin_u1 * sin_sigma - cos_u1 * cos_sigma * cos_alpha1;
+        let lat = (sin_u1 * cos_sigma + cos_u1 * sin_sigma * cos_alpha1)
+            .atan2((1.0 - f) * sin_alpha.hypot(tmp));
+        let lambda =
+            (sin_sigma * sin_alpha1).atan2(cos_u1 * cos_sigma - sin_u1 * sin_sigma * cos_alpha1);
+        let c = f / 16.0 * cos_sq_alpha * (4.0 + f * (4.0 - 3.0 * cos_sq_alpha));
+        let l = lambda
+            - (1.0 - c)
+                * f
+                * sin_alpha
+                * (sigma
+                    + c * sin_sigma
+                        * (cos_2sigma_m
+                            + c * cos_sigma * (-1.0 + 2.0 * cos_2sigma_m * cos_2sigma_m)));
+
+        GeoCoord {
+            lat: lat.to_degrees(),
+            lon: normalize_lon(self.lon + l.to_degrees()),
+            alt: self.alt,
+        }
+    }
+}
+
+// Vincenty's A, the ratio of the geodesic length to the arc on the auxiliary sphere
+fn big_a(u_sq: f64) -> f64 {
+    1.0 + u_sq / 16_384.0 * (4_096.0 + u_sq * (-768.0 + u_sq * (320.0 - 175.0 * u_sq)))
+}
+
+// Vincenty's correction to the arc length on the auxiliary sphere
+fn delta_sigma(u_sq: f64, sin_sigma: f64, cos_sigma: f64, cos_2sigma_m: f64) -> f64 {
+    let b = u_sq / 1_024.0 * (256.0 + u_sq * (-128.0 + u_sq * (74.0 - 47.0 * u_sq)));
+    let cos_sq = cos_2sigma_m * cos_2sigma_m;
+    b * sin_sigma
+        * (cos_2sigma_m
+            + b / 4.0
+                * (cos_sigma * (-1.0 + 2.0 * cos_sq)
+                    - b / 6.0
+                        * cos_2sigma_m
+                        * (-3.0 + 4.0 * sin_sigma * sin_sigma)
+                        * (-3.0 + 4.0 * cos_sq)))
+}
+
+// Radians to degrees in [0, 360)
+fn azimuth(radians: f64) -> f64 {
+    radians.to_degrees().rem_euclid(360.0)
+}
+
+// Degrees into [-180, 180)
+fn normalize_lon(lon: f64) -> f64 {
+    (lon + 180.0).rem_euclid(360.0) - 180.0
+}
+
 impl EcefCoord {
-    pub fn to_geo(&self) -> GeoCoord {
+    /// Geodetic coordinates on the WGS84 ellipsoid
+    pub fn to_geo(&self) -> Result<GeoCoord, GeoError> {
+        self.to_geo_on(&Ellipsoid::WGS84)
+    }
+
+    /// Positions that are not finite or within 100 km of the Earth's center have no
+    /// meaningful geodetic coordinates
+    pub fn check(&self) -> Result<(), GeoError> {
+        if !(self.x.is_finite() && self.y.is_finite() && self.z.is_finite()) {
+            return Err(GeoError::NonFinite);
+        }
+        let radius = (self.x * self.x + self.y * self.y + self.z * self.z).sqrt();
+        if radius < MIN_RADIUS {
+            return Err(GeoError::NearCenter(radius));
+        }
+        Ok(())
+    }
+
+    pub fn to_geo_on(&self, ellipsoid: &Ellipsoid) -> Result<GeoCoord, GeoError> {
         /*! Based on python code to do the conversion */
+        self.check()?;
+        let (a, e_sq) = (ellipsoid.a, ellipsoid.e_sq());
         // Compute longitude in radians
         let lon = self.y.atan2(self.x);
 
@@ -26,106 +389,102 @@ impl EcefCoord {
         let p = (self.x.powi(2) + self.y.powi(2)).sqrt();
 
         // Initial estimate for latitude
-        let theta = self.z.atan2(p * (1.0 - E_SQ));
+        let theta = self.z.atan2(p * (1.0 - e_sq));
         let mut lat =
-            (self.z + E_SQ * A * theta.sin().powi(3)).atan2(p - E_SQ * A * theta.cos().powi(3));
+            (self.z + e_sq * a * theta.sin().powi(3)).atan2(p - e_sq * a * theta.cos().powi(3));
 
         // Iterative calculation to refine latitude
-        loop {
-            let n = A / (1.0 - E_SQ * lat.sin().powi(2)).sqrt();
-            let new_lat = (self.z + E_SQ * n * lat.sin()).atan2(p);
+        for _ in 0..MAX_ITERATIONS {
+            let n = a / (1.0 - e_sq * lat.sin().powi(2)).sqrt();
+            let new_lat = (self.z + e_sq * n * lat.sin()).atan2(p);
 
             // If the latitude change is small, break the loop
-            if (new_lat - lat).abs() < 1e-12 {
+            let converged = (new_lat - lat).abs() < 1e-12;
+            lat = new_lat;
+            if converged {
                 break;
             }
-            lat = new_lat;
         }
 
-        // Calculate radius of curvature in the prime vertical
-        let n = A / (1.0 - E_SQ * lat.sin().powi(2)).sqrt();
-        // Altitude (h) is the radial distance minus the radius of curvature above
-        let alt = p / lat.cos() - n;
+        let alt = ellipsoid.height(p, self.z, lat);
 
         // Convert latitude and longitude from radians to degrees
         let lat_deg = lat.to_degrees(); // lat * (180.0 / PI)
         let lon_deg = lon.to_degrees(); // lon * (180.0 / PI)
 
         // (lat_deg, lon_deg, h)
-        GeoCoord {
+        Ok(GeoCoord {
             lat: lat_deg,
             lon: lon_deg,
             alt,
-        }
+        })
+    }
+
+    pub fn to_geo_olson(&self) -> Result<GeoCoord, GeoError> {
+        self.to_geo_olson_on(&Ellipsoid::WGS84)
     }
 
-    pub fn to_geo_olson(&self) -> GeoCoord {
-        //! Based on C code from [Planet36](https://github.com/planet36/ecef-geodetic/blob/main/olson_1996/olson_1996.c) 
-        let e2 = 6.694_379_990_137_799e-3;
-        let a = 6_378_137.0;
-        let a1 = 4.269_767_270_715_753e4;
-        let a2: f64 = 1.823_091_254_607_545e9;
-	    let a3: f64 = 1.429_172_228_981_241e2;
-        let a4 = 4.557_728_136_518_863e9;
-        let a5 = 4.284_058_993_005_565e4;
-	    let a6 = 9.933_056_200_098_622e-1;
+    pub fn to_geo_olson_on(&self, ellipsoid: &Ellipsoid) -> Result<GeoCoord, GeoError> {
+        //! Based on C code from [Planet36](https://github.com/planet36/ecef-geodetic/blob/main/olson_1996/olson_1996.c)
+        self.check()?;
+        let e2 = ellipsoid.e_sq();
+        let a = ellipsoid.a;
+        let a1 = a * e2;
+        let a2 = a1 * a1;
+        let a3 = a1 * e2 / 2.0;
+        let a4 = 2.5 * a2;
+        let a5 = a1 + a3;
+        let a6 = 1.0 - e2;
 
         let zp = self.z.abs();
-	    let w2 = self.x * self.x + self.y * self.y;
+        let w2 = self.x * self.x + self.y * self.y;
         let w = w2.sqrt();
         let z2 = self.z * self.z;
         let r2 = w2 + z2;
         let r = r2.sqrt();
-        if r < 100_000. {
-            return GeoCoord {
-                lat: 0.0,
-                lon: 0.0,
-                alt: -1.0e7,
-            }
-        }
 
         let lon_deg = self.y.atan2(self.x);
-        let s2 = z2/r2;
-        let c2 = w2/r2;
-        let u = a2/r;
-        let v = a3 - a4/r;
+        let s2 = z2 / r2;
+        let c2 = w2 / r2;
+        let u = a2 / r;
+        let v = a3 - a4 / r;
         let mut lat_deg: f64;
         let c: f64;
         let ss: f64;
-        let s: f64;// = 0.0;
-        
+        let s: f64; // = 0.0;
+
         if c2 > 0.4 {
             s = (zp / r) * (1.0 + c2 * (a1 + u + s2 * v) / r);
             lat_deg = s.asin();
             ss = s * s;
             c = (1.0 - ss).sqrt();
         } else {
-            c = (w/r)*(1.0 - s2*(a5 - u - c2*v)/r);
+            c = (w / r) * (1.0 - s2 * (a5 - u - c2 * v) / r);
             lat_deg = c.acos();
-            ss = 1.0 - c*c;
+            ss = 1.0 - c * c;
             s = ss.sqrt();
         }
 
-        let g = 1. - e2*ss;
+        let g = 1. - e2 * ss;
         let rg = a / g.sqrt();
-        let rf = a6*rg;
-        let u = w - rg*c;
-        let v = zp - rf*s;
-        let f = c*u + s*v;
-        let m = c*v - s*u;
-        let p = m/(rf/g + f);
+        let rf = a6 * rg;
+        let u = w - rg * c;
+        let v = zp - rf * s;
+        let f = c * u + s * v;
+        let m = c * v - s * u;
+        let p = m / (rf / g + f);
         lat_deg += p;
 
-        let alt: f64 = f + m*p/2.0;
+        let alt: f64 = f + m * p / 2.0;
         if self.z < 0.0 {
             lat_deg = -lat_deg;
         }
 
-        GeoCoord {
+        Ok(GeoCoord {
             lat: lat_deg.to_degrees(),
             lon: lon_deg.to_degrees(),
             alt,
-        }
+        })
     }
 }
 
@@ -146,7 +505,7 @@ mod tests {
     #[case(EcefCoord{x: -2694044.4111565403, y: -4266368.805493665, z: 3888310.602276871},
     GeoCoord{lat: 37.80437, lon: -122.27080, alt: 0.00000})]
     fn ecef_to_geo_test(#[case] ecef: EcefCoord, #[case] expected: GeoCoord) {
-        let actual = ecef.to_geo();
+        let actual = ecef.to_geo().unwrap();
         println!("Actual result: {:?}", actual);
         println!("Expect result: {:?}", expected);
 
@@ -182,7 +541,7 @@ mod tests {
     #[case(EcefCoord{x: -2694044.4111565403, y: -4266368.805493665, z: 3888310.602276871},
     GeoCoord{lat: 37.80437, lon: -122.27080, alt: 0.00000})]
     fn ecef_to_geo_olson_test(#[case] ecef: EcefCoord, #[case] expected: GeoCoord) {
-        let actual = ecef.to_geo_olson();
+        let actual = ecef.to_geo_olson().unwrap();
         println!("Actual result: {:?}", actual);
         println!("Expect result: {:?}", expected);
 
@@ -209,4 +568,202 @@ mod tests {
             ulps = 2
         ))
     }
+
+    #[rstest]
+    // On a sphere the latitude is geocentric and the altitude the distance past the radius
+    #[case(Ellipsoid::new(6_371_000.0, 0.0).unwrap(),
+    EcefCoord{x: 0.0, y: 4512048.37075136, z: 4512048.37075136},
+    GeoCoord{lat: 45.0, lon: 90.0, alt: 10_000.0})]
+    // GRS80 differs from WGS84 by a fraction of a millimeter
+    #[case(Ellipsoid::GRS80,
+    EcefCoord{x: -2694044.4111565403, y: -4266368.805493665, z: 3888310.602276871},
+    GeoCoord{lat: 37.80437, lon: -122.27080, alt: 0.00000})]
+    fn ellipsoid_test(
+        #[case] ellipsoid: Ellipsoid,
+        #[case] ecef: EcefCoord,
+        #[case] expected: GeoCoord,
+    ) {
+        for actual in [ecef.to_geo_on(&ellipsoid), ecef.to_geo_olson_on(&ellipsoid)] {
+            let actual = actual.unwrap();
+            assert!(approx_eq!(f64, expected.lat, actual.lat, epsilon = 0.00003));
+            assert!(approx_eq!(f64, expected.lon, actual.lon, epsilon = 0.00003));
+            assert!(approx_eq!(f64, expected.alt, actual.alt, epsilon = 0.001));
+        }
+    }
+
+    #[rstest]
+    // North and south pole on the surface and 100 km above, the polar radius is 6356752.3142 m
+    #[case(EcefCoord{x: 0.0, y: 0.0, z: 6_356_752.314_2}, GeoCoord{lat: 90.0, lon: 0.0, alt: 0.0})]
+    #[case(EcefCoord{x: 0.0, y: 0.0, z: -6_456_752.314_2}, GeoCoord{lat: -90.0, lon: 0.0, alt: 100_000.0})]
+    // Equator on the surface and 100 km above
+    #[case(EcefCoord{x: 6_378_137.0, y: 0.0, z: 0.0}, GeoCoord{lat: 0.0, lon: 0.0, alt: 0.0})]
+    #[case(EcefCoord{x: 0.0, y: 6_478_137.0, z: 0.0}, GeoCoord{lat: 0.0, lon: 90.0, alt: 100_000.0})]
+    fn poles_and_equator_test(#[case] ecef: EcefCoord, #[case] expected: GeoCoord) {
+        for actual in [ecef.to_geo(), ecef.to_geo_olson()] {
+            let actual = actual.unwrap();
+            assert!(approx_eq!(f64, expected.lat, actual.lat, epsilon = 1e-9));
+            assert!(approx_eq!(f64, expected.lon, actual.lon, epsilon = 1e-9));
+            assert!(approx_eq!(f64, expected.alt, actual.alt, epsilon = 1e-4));
+        }
+    }
+
+    // Vincenty's own example, Flinders Peak to Buninyong
+    #[test]
+    fn geodesic_inverse_test() {
+        let flinders_peak = GeoCoord {
+            lat: -37.951_033_416,
+            lon: 144.424_867_889,
+            alt: 0.0,
+        };
+        let buninyong = GeoCoord {
+            lat: -37.652_821_139,
+            lon: 143.926_495_528,
+            alt: 0.0,
+        };
+
+        let actual = flinders_peak.geodesic_inverse(&buninyong, &Ellipsoid::GRS80);
+
+        assert!(approx_eq!(
+            f64,
+            54_972.271,
+            actual.distance,
+            epsilon = 0.001
+        ));
+        // 306°52'05.37" and 307°10'25.07", to the 0.01" they are given in
+        assert!(approx_eq!(
+            f64,
+            306.868_158_3,
+            actual.initial_azimuth,
+            epsilon = 3e-6
+        ));
+        assert!(approx_eq!(
+            f64,
+            307.173_630_6,
+            actual.final_azimuth,
+            epsilon = 3e-6
+        ));
+    }
+
+    #[rstest]
+    #[case(GeoCoord{lat: 31.4, lon: -104.8, alt: 0.0}, 30.0, 3_300.0)]
+    #[case(GeoCoord{lat: -60.0, lon: 170.0, alt: 0.0}, 250.0, 5_000_000.0)]
+    #[case(GeoCoord{lat: 0.0, lon: 0.0, alt: 0.0}, 90.0, 10_000_000.0)]
+    fn geodesic_round_trip(#[case] from: GeoCoord, #[case] azimuth: f64, #[case] distance: f64) {
+        let to = from.geodesic_direct(azimuth, distance, &Ellipsoid::WGS84);
+        let actual = from.geodesic_inverse(&to, &Ellipsoid::WGS84);
+        assert!(!actual.spherical);
+        assert!(approx_eq!(f64, distance, actual.distance, epsilon = 1e-4));
+        assert!(approx_eq!(
+            f64,
+            azimuth,
+            actual.initial_azimuth,
+            epsilon = 1e-8
+        ));
+
+        let radius = Ellipsoid::WGS84.mean_radius();
+        let to = from.great_circle_direct(azimuth, distance, radius);
+        let actual = from.great_circle_inverse(&to, radius);
+        assert!(approx_eq!(f64, distance, actual.distance, epsilon = 1e-4));
+        assert!(approx_eq!(
+            f64,
+            azimuth,
+            actual.initial_azimuth,
+            epsilon = 1e-8
+        ));
+    }
+
+    #[test]
+    fn great_circle_test() {
+        let radius = 6_371_000.0;
+        let actual = GeoCoord {
+            lat: 0.0,
+            lon: 0.0,
+            alt: 0.0,
+        }
+        .great_circle_inverse(
+            &GeoCoord {
+                lat: 0.0,
+                lon: 90.0,
+                alt: 0.0,
+            },
+            radius,
+        );
+        assert!(approx_eq!(
+            f64,
+            radius * PI / 2.0,
+            actual.distance,
+            epsilon = 1e-6
+        ));
+        assert!(approx_eq!(
+            f64,
+            90.0,
+            actual.initial_azimuth,
+            epsilon = 1e-9
+        ));
+        assert!(approx_eq!(f64, 90.0, actual.final_azimuth, epsilon = 1e-9));
+    }
+
+    #[test]
+    fn nearly_antipodal_geodesic() {
+        let from = GeoCoord {
+            lat: 0.0,
+            lon: 0.0,
+            alt: 0.0,
+        };
+        let to = GeoCoord {
+            lat: 0.5,
+            lon: 179.7,
+            alt: 0.0,
+        };
+        let actual = from.geodesic_inverse(&to, &Ellipsoid::WGS84);
+
+        // Vincenty fails here, the great circle is within 0.5% of Karney's 19936288.579 m
+        assert!(actual.spherical);
+        assert!(approx_eq!(
+            f64,
+            19_936_288.579,
+            actual.distance,
+            epsilon = 0.005 * 19_936_288.579
+        ));
+        assert_eq!(
+            from.great_circle_inverse(&to, Ellipsoid::WGS84.mean_radius()),
+            actual
+        );
+    }
+
+    #[rstest]
+    #[case(6_378_137.0, 298.257_223_563, true)]
+    #[case(6_371_000.0, 0.0, true)]
+    #[case(0.0, 298.0, false)]
+    #[case(-6_378_137.0, 298.0, false)]
+    #[case(f64::NAN, 298.0, false)]
+    #[case(6_378_137.0, 0.5, false)]
+    #[case(6_378_137.0, 1.0, false)]
+    #[case(6_378_137.0, -298.0, false)]
+    #[case(6_378_137.0, f64::INFINITY, false)]
+    fn new_ellipsoid(#[case] a: f64, #[case] inverse_flattening: f64, #[case] valid: bool) {
+        assert_eq!(valid, Ellipsoid::new(a, inverse_flattening).is_ok());
+    }
+
+    #[rstest]
+    #[case(EcefCoord{x: 0.0, y: 0.0, z: 0.0}, GeoError::NearCenter(0.0))]
+    #[case(EcefCoord{x: 30_000.0, y: 0.0, z: 40_000.0}, GeoError::NearCenter(50_000.0))]
+    #[case(EcefCoord{x: f64::NAN, y: 0.0, z: 6_356_752.0}, GeoError::NonFinite)]
+    #[case(EcefCoord{x: 0.0, y: f64::INFINITY, z: 0.0}, GeoError::NonFinite)]
+    fn degenerate_test(#[case] ecef: EcefCoord, #[case] expected: GeoError) {
+        assert_eq!(Err(expected), ecef.to_geo());
+        assert_eq!(Err(expected), ecef.to_geo_olson());
+    }
+
+    #[rstest]
+    #[case(GeoCoord{lat: 0.0, lon: 0.0, alt: 0.0}, [1.0, 0.0, 0.0], [0.0, 0.0, 1.0])]
+    #[case(GeoCoord{lat: 0.0, lon: 0.0, alt: 0.0}, [0.0, 1.0, 0.0], [1.0, 0.0, 0.0])]
+    #[case(GeoCoord{lat: 90.0, lon: 0.0, alt: 0.0}, [0.0, 0.0, 1.0], [0.0, 0.0, 1.0])]
+    #[case(GeoCoord{lat: 45.0, lon: 90.0, alt: 0.0}, [0.0, 0.0, 1.0], [0.0, 0.5f64.sqrt(), 0.5f64.sqrt()])]
+    fn ecef_to_enu_test(#[case] geo: GeoCoord, #[case] ecef: [f64; 3], #[case] expected: [f64; 3]) {
+        let actual = geo.ecef_to_enu(ecef[0], ecef[1], ecef[2]);
+        for (expected, actual) in expected.iter().zip(actual) {
+            assert!(approx_eq!(f64, *expected, actual, epsilon = 1e-12));
+        }
+    }
 }
@@ -1,8 +1,12 @@
+use blue_telemetry_tui::coord::EcefCoord;
 use csv::ReaderBuilder;
 use std::error::Error;
-use std::fs::File;
 
-#[derive(Debug)]
+use crate::input::open_input;
+use crate::quality::QualityFlags;
+use crate::quat;
+
+#[derive(Debug, Clone)]
 pub struct TelemetryRecord {
     pub timestamp_ns: u64,
     pub pos_x: f64,
@@ -11,21 +15,54 @@ pub struct TelemetryRecord {
     pub vel_x: f64,
     pub vel_y: f64,
     pub vel_z: f64,
+    /// Attitude quaternion, identity when the source has no attitude columns
+    pub quat: [f64; 4],
+    /// Plausibility checks this record failed
+    pub flags: QualityFlags,
+    /// Channels merged in from other sensor files, `None` where a sensor had no data
+    pub sensors: Vec<Option<f64>>,
+}
+
+impl Default for TelemetryRecord {
+    fn default() -> Self {
+        TelemetryRecord {
+            timestamp_ns: 0,
+            pos_x: 0.0,
+            pos_y: 0.0,
+            pos_z: 0.0,
+            vel_x: 0.0,
+            vel_y: 0.0,
+            vel_z: 0.0,
+            quat: quat::IDENTITY,
+            flags: QualityFlags::NONE,
+            sensors: Vec::new(),
+        }
+    }
+}
+
+impl TelemetryRecord {
+    pub fn ecef(&self) -> EcefCoord {
+        EcefCoord {
+            x: self.pos_x,
+            y: self.pos_y,
+            z: self.pos_z,
+        }
+    }
+
+    /// Magnitude of the velocity in m/s
+    pub fn speed(&self) -> f64 {
+        (self.vel_x.powi(2) + self.vel_y.powi(2) + self.vel_z.powi(2)).sqrt()
+    }
 }
 
 #[allow(clippy::cast_possible_truncation)]
 #[allow(clippy::cast_sign_loss)]
-pub fn read_csv_and_chunk(
-    file_path: &str,
-    time_chunk_duration: u64,
-) -> Result<Vec<Vec<TelemetryRecord>>, Box<dyn Error>> {
-    // Open the CSV file
-    let file = File::open(file_path)?;
+pub fn read_csv(file_path: &str) -> Result<Vec<TelemetryRecord>, Box<dyn Error>> {
+    // Open the CSV file, decompressing `.csv.gz` and `.csv.zst` while reading
+    let file = open_input(file_path)?;
     let mut rdr = ReaderBuilder::new().has_headers(true).from_reader(file);
 
-    let mut current_chunk: Vec<TelemetryRecord> = Vec::new();
-    let mut previous_timestamp: Option<u64> = None;
-    let mut chunks: Vec<Vec<TelemetryRecord>> = Vec::new();
+    let mut records: Vec<TelemetryRecord> = Vec::new();
 
     // Iterate through csv file
     for result in rdr.records() {
@@ -41,7 +78,18 @@ pub fn read_csv_and_chunk(
         let vel_x = record.get(4).unwrap().parse::<f64>().unwrap();
         let vel_y = record.get(5).unwrap().parse::<f64>().unwrap();
         let vel_z = record.get(6).unwrap().parse::<f64>().unwrap();
-        let row = TelemetryRecord {
+        // Attitude columns are optional
+        let quat = if record.len() >= 11 {
+            [
+                record.get(7).unwrap().parse::<f64>()?,
+                record.get(8).unwrap().parse::<f64>()?,
+                record.get(9).unwrap().parse::<f64>()?,
+                record.get(10).unwrap().parse::<f64>()?,
+            ]
+        } else {
+            quat::IDENTITY
+        };
+        records.push(TelemetryRecord {
             timestamp_ns,
             pos_x,
             pos_y,
@@ -49,59 +97,36 @@ pub fn read_csv_and_chunk(
             vel_x,
             vel_y,
             vel_z,
-        };
-
-        // If it's the first row, start a new chunk
-        if previous_timestamp.is_none() {
-            current_chunk.push(row);
-            previous_timestamp = Some(timestamp_ns);
-            continue;
-        }
-
-        // Check the time difference from the previous timestamp
-        if let Some(prev_ts) = previous_timestamp {
-            let time_diff = timestamp_ns.saturating_sub(prev_ts);
-
-            // If time difference exceeds the chunk duration, start a new chunk
-            if time_diff > time_chunk_duration {
-                // push current chunk since we are done with it.
-                chunks.push(current_chunk);
-
-                // Create a new chunk to populate
-                current_chunk = Vec::new();
-                current_chunk.push(row);
-
-                // Update the previous timestamp
-                previous_timestamp = Some(timestamp_ns);
-            } else {
-                current_chunk.push(row); // Add the row to the current chunk
-            }
-        }
-    }
-
-    // Add the last chunk to the vec
-    if !current_chunk.is_empty() {
-        chunks.push(current_chunk);
+            quat,
+            flags: QualityFlags::NONE,
+            sensors: Vec::new(),
+        });
     }
 
-    Ok(chunks)
+    Ok(records)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-
-    #[test]
-    fn test_csv_chunked_read() {
-        let file_path = "data/test.csv";
-
+    use crate::chunking::{chunk_records, ChunkStrategy};
+    use rstest::rstest;
+
+    #[rstest]
+    #[case("data/test.csv")]
+    #[case("data/test.csv.gz")]
+    #[case("data/test.csv.zst")]
+    fn test_csv_chunked_read(#[case] file_path: &str) {
         let time_chunk_duration = 1_000_000_000;
-        let actual = read_csv_and_chunk(file_path, time_chunk_duration).unwrap();
+        let strategy = ChunkStrategy::Drift {
+            duration_ns: time_chunk_duration,
+        };
+        let actual = chunk_records(read_csv(file_path).unwrap(), strategy).unwrap();
 
         assert_eq!(3, actual.len());
 
-        for (i, chunk) in actual.iter().enumerate() {
-            assert_eq!(2, chunk.len());
+        for chunk in &actual {
+            assert_eq!(2, chunk.records.len());
         }
     }
 }
@@ -0,0 +1,157 @@
+use std::error::Error;
+use std::fmt;
+
+use crate::csv_reader::TelemetryRecord;
+use crate::gravity::{acceleration, EARTH_RATE};
+use crate::merge::SensorSeries;
+use crate::quat;
+
+/// Integrated IMU output over the interval ending at `timestamp_ns`, in the body frame
+#[derive(Debug, Default, PartialEq, Clone, Copy)]
+pub struct ImuSample {
+    pub timestamp_ns: u64,
+    /// Change in velocity from specific force in m/s
+    pub delta_vel: [f64; 3],
+    /// Rotation angle in radians
+    pub delta_angle: [f64; 3],
+}
+
+#[derive(Debug)]
+pub struct ImuFormatError(String);
+
+impl fmt::Display for ImuFormatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid IMU file: {}", self.0)
+    }
+}
+
+impl Error for ImuFormatError {}
+
+/// Read IMU samples from a sensor file whose channels are the delta-velocity
+/// x, y, z followed by the delta-angle x, y, z
+pub fn imu_samples(series: &SensorSeries) -> Result<Vec<ImuSample>, Box<dyn Error>> {
+    if series.channels.len() < 6 {
+        return Err(ImuFormatError(format!(
+            "{} has {} channels, expected delta-velocity and delta-angle x, y, z",
+            series.name,
+            series.channels.len()
+        ))
+        .into());
+    }
+
+    Ok(series
+        .timestamps
+        .iter()
+        .zip(&series.values)
+        .map(|(timestamp_ns, values)| ImuSample {
+            timestamp_ns: *timestamp_ns,
+            delta_vel: [values[0], values[1], values[2]],
+            delta_angle: [values[3], values[4], values[5]],
+        })
+        .collect())
+}
+
+/// Propagate position, velocity and attitude in ECEF from `initial` using only the
+/// IMU samples after it. The attitude is the body to ECEF quaternion, scalar last.
+#[allow(clippy::cast_precision_loss)]
+pub fn dead_reckon(initial: &TelemetryRecord, samples: &[ImuSample]) -> Vec<TelemetryRecord> {
+    let mut state = TelemetryRecord {
+        sensors: Vec::new(),
+        quat: quat::normalize(initial.quat),
+        ..initial.clone()
+    };
+    let mut propagated: Vec<TelemetryRecord> = vec![state.clone()];
+    // Attitude after the body turns by `delta_angle` while ECEF turns by the Earth's rotation over `dt`
+    let turn = |attitude: &[f64; 4], delta_angle: &[f64; 3], dt: f64| {
+        let body_turn = quat::from_rotation_vector(delta_angle);
+        let earth_turn = quat::from_rotation_vector(&[0.0, 0.0, -EARTH_RATE * dt]);
+        quat::normalize(quat::multiply(
+            &earth_turn,
+            &quat::multiply(attitude, &body_turn),
+        ))
+    };
+
+    for sample in samples
+        .iter()
+        .skip_while(|sample| sample.timestamp_ns <= initial.timestamp_ns)
+    {
+        let dt = sample.timestamp_ns.saturating_sub(state.timestamp_ns) as f64 / 1e9;
+        let pos = [state.pos_x, state.pos_y, state.pos_z];
+        let vel = [state.vel_x, state.vel_y, state.vel_z];
+
+        // Specific force rotated with the attitude halfway through the interval
+        let mid_attitude = turn(&state.quat, &sample.delta_angle.map(|c| 0.5 * c), 0.5 * dt);
+        let delta_vel = quat::rotate(&mid_attitude, &sample.delta_vel);
+
+        let accel = acceleration(&pos, &vel);
+        let new_vel: [f64; 3] = std::array::from_fn(|i| vel[i] + delta_vel[i] + accel[i] * dt);
+        let new_pos: [f64; 3] = std::array::from_fn(|i| pos[i] + 0.5 * (vel[i] + new_vel[i]) * dt);
+
+        state.quat = turn(&state.quat, &sample.delta_angle, dt);
+        state.timestamp_ns = sample.timestamp_ns;
+        [state.pos_x, state.pos_y, state.pos_z] = new_pos;
+        [state.vel_x, state.vel_y, state.vel_z] = new_vel;
+        propagated.push(state.clone());
+    }
+
+    propagated
+}
+
+/***************************
+           Tests
+***************************/
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use blue_telemetry_tui::coord::Ellipsoid;
+    use float_cmp::approx_eq;
+    use rstest::rstest;
+
+    const A: f64 = Ellipsoid::WGS84.a;
+
+    // Standing still on the equator with the body axes along ECEF, sampled at 100 Hz for 10 s.
+    // The accelerometer feels the ground pushing up against gravity and the gyro the Earth's rotation
+    fn stationary_samples(accel_bias: [f64; 3]) -> Vec<ImuSample> {
+        let dt = 0.01;
+        let specific_force = acceleration(&[A, 0.0, 0.0], &[0.0; 3]).map(|c| -c);
+        (1..=1_000u64)
+            .map(|i| ImuSample {
+                timestamp_ns: i * 10_000_000,
+                delta_vel: std::array::from_fn(|j| (specific_force[j] + accel_bias[j]) * dt),
+                delta_angle: [0.0, 0.0, EARTH_RATE * dt],
+            })
+            .collect()
+    }
+
+    // An east bias of 0.01 m/s^2 drifts half a meter in 10 s
+    #[rstest]
+    #[case([0.0; 3], 0.0)]
+    #[case([0.0, 0.01, 0.0], 0.5)]
+    fn stationary_drift(#[case] accel_bias: [f64; 3], #[case] expected_drift: f64) {
+        let initial = TelemetryRecord {
+            pos_x: A,
+            ..TelemetryRecord::default()
+        };
+
+        let propagated = dead_reckon(&initial, &stationary_samples(accel_bias));
+
+        assert_eq!(1_001, propagated.len());
+        let last = propagated.last().unwrap();
+        assert_eq!(10_000_000_000, last.timestamp_ns);
+        assert!(approx_eq!(f64, A, last.pos_x, epsilon = 1e-3));
+        assert!(approx_eq!(f64, expected_drift, last.pos_y, epsilon = 1e-3));
+        for (expected, actual) in quat::IDENTITY.iter().zip(last.quat) {
+            assert!(approx_eq!(f64, *expected, actual, epsilon = 1e-12));
+        }
+    }
+
+    #[test]
+    fn imu_needs_six_channels() {
+        let series = SensorSeries {
+            name: "imu".to_string(),
+            channels: vec!["dv_x".to_string()],
+            ..SensorSeries::default()
+        };
+        assert!(imu_samples(&series).is_err());
+    }
+}
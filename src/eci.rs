@@ -0,0 +1,339 @@
+//! ECEF to Earth centered inertial (GCRF / J2000) conversion from the TAI timestamps.
+//! Polar motion is ignored, it moves positions on the surface by around 10 m.
+
+use clap::ValueEnum;
+use std::f64::consts::{PI, TAU};
+
+use crate::gravity::EARTH_RATE;
+
+type Mat3 = [[f64; 3]; 3];
+
+const UNIX_EPOCH_JD: f64 = 2_440_587.5;
+const J2000_JD: f64 = 2_451_545.0;
+const SECONDS_PER_DAY: f64 = 86_400.0;
+const DAYS_PER_CENTURY: f64 = 36_525.0;
+const TT_MINUS_TAI: f64 = 32.184;
+const ARCSEC: f64 = PI / (180.0 * 3_600.0);
+
+// UTC unix time each leap second took effect and TAI - UTC from then on
+const LEAP_SECONDS: [(u64, f64); 28] = [
+    (63_072_000, 10.0),
+    (78_796_800, 11.0),
+    (94_694_400, 12.0),
+    (126_230_400, 13.0),
+    (157_766_400, 14.0),
+    (189_302_400, 15.0),
+    (220_924_800, 16.0),
+    (252_460_800, 17.0),
+    (283_996_800, 18.0),
+    (315_532_800, 19.0),
+    (362_793_600, 20.0),
+    (394_329_600, 21.0),
+    (425_865_600, 22.0),
+    (489_024_000, 23.0),
+    (567_993_600, 24.0),
+    (631_152_000, 25.0),
+    (662_688_000, 26.0),
+    (709_948_800, 27.0),
+    (741_484_800, 28.0),
+    (773_020_800, 29.0),
+    (820_454_400, 30.0),
+    (867_715_200, 31.0),
+    (915_148_800, 32.0),
+    (1_136_073_600, 33.0),
+    (1_230_768_000, 34.0),
+    (1_341_100_800, 35.0),
+    (1_435_708_800, 36.0),
+    (1_483_228_800, 37.0),
+];
+
+// Largest terms of the IAU 1980 nutation series. Multiples of the fundamental arguments
+// l, l', F, D, Omega then the longitude and obliquity coefficients in 0.0001 arcseconds
+#[rustfmt::skip]
+const NUTATION_TERMS: [([f64; 5], [f64; 4]); 18] = [
+    ([0.0, 0.0, 0.0, 0.0, 1.0], [-171_996.0, -174.2, 92_025.0, 8.9]),
+    ([0.0, 0.0, 2.0, -2.0, 2.0], [-13_187.0, -1.6, 5_736.0, -3.1]),
+    ([0.0, 0.0, 2.0, 0.0, 2.0], [-2_274.0, -0.2, 977.0, -0.5]),
+    ([0.0, 0.0, 0.0, 0.0, 2.0], [2_062.0, 0.2, -895.0, 0.5]),
+    ([0.0, 1.0, 0.0, 0.0, 0.0], [1_426.0, -3.4, 54.0, -0.1]),
+    ([1.0, 0.0, 0.0, 0.0, 0.0], [712.0, 0.1, -7.0, 0.0]),
+    ([0.0, 1.0, 2.0, -2.0, 2.0], [-517.0, 1.2, 224.0, -0.6]),
+    ([0.0, 0.0, 2.0, 0.0, 1.0], [-386.0, -0.4, 200.0, 0.0]),
+    ([1.0, 0.0, 2.0, 0.0, 2.0], [-301.0, 0.0, 129.0, -0.1]),
+    ([0.0, -1.0, 2.0, -2.0, 2.0], [217.0, -0.5, -95.0, 0.3]),
+    ([1.0, 0.0, 0.0, -2.0, 0.0], [-158.0, 0.0, -1.0, 0.0]),
+    ([0.0, 0.0, 2.0, -2.0, 1.0], [129.0, 0.1, -70.0, 0.0]),
+    ([-1.0, 0.0, 2.0, 0.0, 2.0], [123.0, 0.0, -53.0, 0.0]),
+    ([0.0, 0.0, 0.0, 2.0, 0.0], [63.0, 0.0, -2.0, 0.0]),
+    ([1.0, 0.0, 0.0, 0.0, 1.0], [63.0, 0.1, -33.0, 0.0]),
+    ([-1.0, 0.0, 2.0, 2.0, 2.0], [-59.0, 0.0, 26.0, 0.0]),
+    ([-1.0, 0.0, 0.0, 0.0, 1.0], [-58.0, -0.1, 32.0, 0.0]),
+    ([1.0, 0.0, 2.0, 0.0, 1.0], [-51.0, 0.0, 27.0, 0.0]),
+];
+
+/// How the Earth's orientation is modeled
+#[derive(ValueEnum, Debug, Default, PartialEq, Clone, Copy)]
+pub enum EciModel {
+    /// Only the Earth rotation angle, giving the intermediate frame of date
+    Rotation,
+    /// IAU-76/FK5 precession and IAU 1980 nutation on top of sidereal time, giving J2000
+    #[default]
+    Full,
+}
+
+#[derive(Debug, Default, PartialEq, Clone, Copy)]
+pub struct EciConfig {
+    pub model: EciModel,
+    /// UT1 - UTC in seconds from the IERS bulletins, zero is good to 0.9 s
+    pub dut1: f64,
+}
+
+/// Inertial position and velocity
+#[derive(Debug, Default, PartialEq, Clone, Copy)]
+pub struct EciState {
+    pub pos: [f64; 3],
+    pub vel: [f64; 3],
+}
+
+impl EciState {
+    pub fn speed(&self) -> f64 {
+        dot(&self.vel, &self.vel).sqrt()
+    }
+
+    /// Angle of the inertial velocity above the local horizontal in degrees
+    pub fn flight_path_angle(&self) -> f64 {
+        let r = dot(&self.pos, &self.pos).sqrt();
+        (dot(&self.pos, &self.vel) / (r * self.speed()))
+            .asin()
+            .to_degrees()
+    }
+}
+
+/// TAI - UTC in seconds at a TAI time in seconds since 1970
+pub fn tai_minus_utc(tai_seconds: f64) -> f64 {
+    LEAP_SECONDS
+        .iter()
+        .rev()
+        .find(|(utc_start, offset)| tai_seconds - offset >= *utc_start as f64)
+        .map_or(0.0, |(_, offset)| *offset)
+}
+
+/// Earth rotation angle in radians at a UT1 Julian date
+pub fn earth_rotation_angle(jd_ut1: f64) -> f64 {
+    let days = jd_ut1 - J2000_JD;
+    (TAU * (0.779_057_273_264 + 0.002_737_811_911_354_48 * days + days.fract())).rem_euclid(TAU)
+}
+
+/// Greenwich mean sidereal time in radians at a UT1 Julian date, IAU-82
+pub fn gmst(jd_ut1: f64) -> f64 {
+    let t = (jd_ut1 - J2000_JD) / DAYS_PER_CENTURY;
+    let seconds = 67_310.548_41 + (876_600.0 * 3_600.0 + 8_640_184.812_866) * t + 0.093_104 * t * t
+        - 6.2e-6 * t * t * t;
+    (seconds.rem_euclid(SECONDS_PER_DAY) / SECONDS_PER_DAY * TAU).rem_euclid(TAU)
+}
+
+/// Rotate an ECEF position and velocity at a TAI timestamp into the inertial frame
+#[allow(clippy::cast_precision_loss)]
+pub fn ecef_to_eci(
+    timestamp_ns: u64,
+    pos: &[f64; 3],
+    vel: &[f64; 3],
+    config: &EciConfig,
+) -> EciState {
+    let tai = timestamp_ns as f64 / 1e9;
+    let utc = tai - tai_minus_utc(tai);
+    let jd_ut1 = (utc + config.dut1) / SECONDS_PER_DAY + UNIX_EPOCH_JD;
+    let jd_tt = (tai + TT_MINUS_TAI) / SECONDS_PER_DAY + UNIX_EPOCH_JD;
+
+    let matrix = match config.model {
+        EciModel::Rotation => rot3(-earth_rotation_angle(jd_ut1)),
+        EciModel::Full => {
+            let t = (jd_tt - J2000_JD) / DAYS_PER_CENTURY;
+            let (nutation, equation_of_equinoxes) = nutation(t);
+            let sidereal = rot3(-(gmst(jd_ut1) + equation_of_equinoxes));
+            mat_mul(&mat_mul(&precession(t), &nutation), &sidereal)
+        }
+    };
+
+    // Velocity of the rotating frame itself, w x r
+    let vel = [
+        vel[0] - EARTH_RATE * pos[1],
+        vel[1] + EARTH_RATE * pos[0],
+        vel[2],
+    ];
+    EciState {
+        pos: mat_vec(&matrix, pos),
+        vel: mat_vec(&matrix, &vel),
+    }
+}
+
+/// Mean of date to J2000, IAU-76 precession for `t` Julian centuries of TT since J2000
+fn precession(t: f64) -> Mat3 {
+    let (t2, t3) = (t * t, t * t * t);
+    let zeta = (2_306.218_1 * t + 0.301_88 * t2 + 0.017_998 * t3) * ARCSEC;
+    let theta = (2_004.310_9 * t - 0.426_65 * t2 - 0.041_833 * t3) * ARCSEC;
+    let z = (2_306.218_1 * t + 1.094_68 * t2 + 0.018_203 * t3) * ARCSEC;
+    mat_mul(&mat_mul(&rot3(zeta), &rot2(-theta)), &rot3(z))
+}
+
+/// True of date to mean of date and the equation of the equinoxes in radians
+fn nutation(t: f64) -> (Mat3, f64) {
+    let (t2, t3) = (t * t, t * t * t);
+    let revolution = 1_296_000.0;
+    // Fundamental arguments of the Moon and Sun in arcseconds
+    let arguments = [
+        485_866.733 + (1_325.0 * revolution + 715_922.633) * t + 31.310 * t2 + 0.064 * t3,
+        1_287_099.804 + (99.0 * revolution + 1_292_581.224) * t - 0.577 * t2 - 0.012 * t3,
+        335_778.877 + (1_342.0 * revolution + 295_263.137) * t - 13.257 * t2 + 0.011 * t3,
+        1_072_261.307 + (1_236.0 * revolution + 1_105_601.328) * t - 6.891 * t2 + 0.019 * t3,
+        450_160.280 - (5.0 * revolution + 482_890.539) * t + 7.455 * t2 + 0.008 * t3,
+    ]
+    .map(|arcsec| (arcsec * ARCSEC).rem_euclid(TAU));
+
+    let (mut d_psi, mut d_eps) = (0.0, 0.0);
+    for (multiples, [psi, psi_t, eps, eps_t]) in NUTATION_TERMS {
+        let angle: f64 = multiples.iter().zip(&arguments).map(|(m, a)| m * a).sum();
+        d_psi += (psi + psi_t * t) * angle.sin();
+        d_eps += (eps + eps_t * t) * angle.cos();
+    }
+    let (d_psi, d_eps) = (d_psi * 1e-4 * ARCSEC, d_eps * 1e-4 * ARCSEC);
+
+    let mean_obliquity = (84_381.448 - 46.815_0 * t - 0.000_59 * t2 + 0.001_813 * t3) * ARCSEC;
+    let true_obliquity = mean_obliquity + d_eps;
+    let omega = arguments[4];
+    let equation_of_equinoxes = d_psi * mean_obliquity.cos()
+        + (0.002_64 * omega.sin() + 0.000_063 * (2.0 * omega).sin()) * ARCSEC;
+
+    let matrix = mat_mul(
+        &mat_mul(&rot1(-mean_obliquity), &rot3(d_psi)),
+        &rot1(true_obliquity),
+    );
+    (matrix, equation_of_equinoxes)
+}
+
+// Frame rotations, turning the axes rather than the vector
+fn rot1(angle: f64) -> Mat3 {
+    let (sin, cos) = angle.sin_cos();
+    [[1.0, 0.0, 0.0], [0.0, cos, sin], [0.0, -sin, cos]]
+}
+
+fn rot2(angle: f64) -> Mat3 {
+    let (sin, cos) = angle.sin_cos();
+    [[cos, 0.0, -sin], [0.0, 1.0, 0.0], [sin, 0.0, cos]]
+}
+
+fn rot3(angle: f64) -> Mat3 {
+    let (sin, cos) = angle.sin_cos();
+    [[cos, sin, 0.0], [-sin, cos, 0.0], [0.0, 0.0, 1.0]]
+}
+
+fn mat_mul(a: &Mat3, b: &Mat3) -> Mat3 {
+    std::array::from_fn(|i| std::array::from_fn(|j| (0..3).map(|k| a[i][k] * b[k][j]).sum()))
+}
+
+fn mat_vec(a: &Mat3, v: &[f64; 3]) -> [f64; 3] {
+    std::array::from_fn(|i| dot(&a[i], v))
+}
+
+fn dot(a: &[f64; 3], b: &[f64; 3]) -> f64 {
+    a.iter().zip(b).map(|(a, b)| a * b).sum()
+}
+
+/***************************
+           Tests
+***************************/
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use float_cmp::approx_eq;
+    use rstest::rstest;
+
+    #[rstest]
+    // 2004 and 2020, the NS-13 flight
+    #[case(1_081_237_920.0, 32.0)]
+    #[case(1_602_596_010.0, 37.0)]
+    // The last second before and the first second after the 2017 leap second
+    #[case(1_483_228_835.0, 36.0)]
+    #[case(1_483_228_837.0, 37.0)]
+    #[case(0.0, 0.0)]
+    fn leap_seconds(#[case] tai_seconds: f64, #[case] expected: f64) {
+        assert_eq!(expected, tai_minus_utc(tai_seconds));
+    }
+
+    // At J2000 both are 280.46061837504 degrees
+    #[test]
+    fn sidereal_time_at_j2000() {
+        let expected = 280.460_618_375_04;
+        assert!(approx_eq!(
+            f64,
+            expected,
+            gmst(J2000_JD).to_degrees(),
+            epsilon = 1e-8
+        ));
+        assert!(approx_eq!(
+            f64,
+            expected,
+            earth_rotation_angle(J2000_JD).to_degrees(),
+            epsilon = 1e-8
+        ));
+    }
+
+    // Vallado, Fundamentals of Astrodynamics and Applications, example 3-15.
+    // 2004 April 6 07:51:28.386009 UTC with UT1 - UTC = -0.4399619 s
+    #[test]
+    fn vallado_example() {
+        let timestamp_ns = 1_081_237_920_386_009_000;
+        let config = EciConfig {
+            model: EciModel::Full,
+            dut1: -0.439_961_9,
+        };
+        let pos = [-1_033_479.383_0, 7_901_295.275_4, 6_380_356.595_8];
+        let vel = [-3_225.636_520, -2_872.451_450, 5_531.924_446];
+
+        let actual = ecef_to_eci(timestamp_ns, &pos, &vel, &config);
+
+        // Polar motion, which is left out, accounts for most of the difference
+        let expected_pos = [5_102_508.958, 6_123_011.401, 6_378_136.928];
+        let expected_vel = [-4_743.220_16, 790.536_50, 5_533.755_28];
+        for (expected, actual) in expected_pos.iter().zip(actual.pos) {
+            assert!(approx_eq!(f64, *expected, actual, epsilon = 15.0));
+        }
+        for (expected, actual) in expected_vel.iter().zip(actual.vel) {
+            assert!(approx_eq!(f64, *expected, actual, epsilon = 0.05));
+        }
+    }
+
+    #[test]
+    fn rotation_keeps_lengths_and_angles() {
+        let pos = [6_378_137.0, 0.0, 0.0];
+        let vel = [100.0, 0.0, 0.0];
+        let config = EciConfig {
+            model: EciModel::Rotation,
+            dut1: 0.0,
+        };
+
+        let actual = ecef_to_eci(1_602_596_010_219_040_000, &pos, &vel, &config);
+
+        assert!(approx_eq!(
+            f64,
+            6_378_137.0,
+            dot(&actual.pos, &actual.pos).sqrt(),
+            epsilon = 1e-6
+        ));
+        assert_eq!(0.0, actual.pos[2]);
+        // Straight up plus the 465 m/s the equator moves east
+        let eastward = EARTH_RATE * 6_378_137.0;
+        assert!(approx_eq!(
+            f64,
+            (100.0f64.powi(2) + eastward.powi(2)).sqrt(),
+            actual.speed(),
+            epsilon = 1e-9
+        ));
+        assert!(approx_eq!(
+            f64,
+            (100.0 / eastward).atan().to_degrees(),
+            actual.flight_path_angle(),
+            epsilon = 1e-9
+        ));
+    }
+}
@@ -0,0 +1,288 @@
+use blue_telemetry_tui::coord::Ellipsoid;
+
+use crate::csv_reader::TelemetryRecord;
+
+// Speed in m/s above which the vehicle is considered to be moving
+const MOVING_SPEED: f64 = 1.0;
+// Sea level air density in kg/m^3 and scale height in meters for an exponential atmosphere
+const SEA_LEVEL_DENSITY: f64 = 1.225;
+const SCALE_HEIGHT: f64 = 8_500.0;
+
+/// Milestones of a suborbital flight
+#[derive(Debug, PartialEq, Eq, Clone, Copy, PartialOrd, Ord)]
+pub enum EventKind {
+    Liftoff,
+    MaxQ,
+    Meco,
+    Apogee,
+    Landing,
+}
+
+impl EventKind {
+    pub fn label(self) -> &'static str {
+        match self {
+            EventKind::Liftoff => "Liftoff",
+            EventKind::MaxQ => "Max Q",
+            EventKind::Meco => "MECO",
+            EventKind::Apogee => "Apogee",
+            EventKind::Landing => "Landing",
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct FlightEvent {
+    pub kind: EventKind,
+    /// Index of the record the event happened at
+    pub index: usize,
+    pub timestamp_ns: u64,
+}
+
+/// Stretches of the flight between the milestones
+#[derive(Debug, PartialEq, Eq, Clone, Copy, PartialOrd, Ord)]
+pub enum FlightPhase {
+    Prelaunch,
+    PoweredAscent,
+    Coast,
+    Descent,
+    Landed,
+}
+
+impl FlightPhase {
+    pub const ALL: [FlightPhase; 5] = [
+        FlightPhase::Prelaunch,
+        FlightPhase::PoweredAscent,
+        FlightPhase::Coast,
+        FlightPhase::Descent,
+        FlightPhase::Landed,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            FlightPhase::Prelaunch => "Prelaunch",
+            FlightPhase::PoweredAscent => "Powered ascent",
+            FlightPhase::Coast => "Coast",
+            FlightPhase::Descent => "Descent",
+            FlightPhase::Landed => "Landed",
+        }
+    }
+
+    /// The phase the flight is in at `timestamp_ns` given its detected events
+    pub fn at(events: &[FlightEvent], timestamp_ns: u64) -> FlightPhase {
+        let passed = |kind: EventKind| {
+            events
+                .iter()
+                .any(|event| event.kind == kind && event.timestamp_ns <= timestamp_ns)
+        };
+
+        if passed(EventKind::Landing) {
+            FlightPhase::Landed
+        } else if passed(EventKind::Apogee) {
+            FlightPhase::Descent
+        } else if passed(EventKind::Meco) {
+            FlightPhase::Coast
+        } else if passed(EventKind::Liftoff) {
+            FlightPhase::PoweredAscent
+        } else {
+            FlightPhase::Prelaunch
+        }
+    }
+}
+
+/// Air density in kg/m^3 at `alt` meters from an exponential atmosphere
+pub fn air_density(alt: f64) -> f64 {
+    SEA_LEVEL_DENSITY * (-alt / SCALE_HEIGHT).exp()
+}
+
+/// Find the flight milestones from the speed and altitude profile of the records,
+/// with altitudes above `ellipsoid`
+pub fn detect_events(records: &[TelemetryRecord], ellipsoid: &Ellipsoid) -> Vec<FlightEvent> {
+    let speeds: Vec<f64> = records.iter().map(TelemetryRecord::speed).collect();
+    let altitudes: Vec<f64> = records
+        .iter()
+        // Positions without an altitude are NaN, which argmax skips
+        .map(|record| {
+            record
+                .ecef()
+                .to_geo_on(ellipsoid)
+                .map_or(f64::NAN, |geo| geo.alt)
+        })
+        .collect();
+
+    let Some(liftoff) = speeds.iter().position(|speed| *speed > MOVING_SPEED) else {
+        return Vec::new();
+    };
+    let apogee = argmax(&altitudes, liftoff, records.len());
+    // The vehicle only slows down after the engine cuts off
+    let meco = argmax(&speeds, liftoff, apogee + 1);
+    let dynamic_pressure: Vec<f64> = speeds
+        .iter()
+        .zip(&altitudes)
+        .map(|(speed, alt)| 0.5 * air_density(*alt) * speed * speed)
+        .collect();
+    let max_q = argmax(&dynamic_pressure, liftoff, meco + 1);
+
+    let mut events = vec![
+        (EventKind::Liftoff, liftoff),
+        (EventKind::MaxQ, max_q),
+        (EventKind::Meco, meco),
+        (EventKind::Apogee, apogee),
+    ];
+    if let Some(offset) = speeds[apogee..]
+        .iter()
+        .zip(&altitudes[apogee..])
+        .position(|(speed, alt)| *speed < MOVING_SPEED && *alt < altitudes[apogee])
+    {
+        events.push((EventKind::Landing, apogee + offset));
+    }
+
+    events
+        .into_iter()
+        .map(|(kind, index)| FlightEvent {
+            kind,
+            index,
+            timestamp_ns: records[index].timestamp_ns,
+        })
+        .collect()
+}
+
+/// Height of the launch pad above the ellipsoid in meters, the mean altitude of the records
+/// sitting still before liftoff. None when the data starts after liftoff.
+#[allow(clippy::cast_precision_loss)]
+pub fn pad_altitude(
+    records: &[TelemetryRecord],
+    events: &[FlightEvent],
+    ellipsoid: &Ellipsoid,
+) -> Option<f64> {
+    let liftoff = events
+        .iter()
+        .find(|event| event.kind == EventKind::Liftoff)
+        .map_or(records.len(), |event| event.index);
+    let altitudes: Vec<f64> = records[..liftoff]
+        .iter()
+        .filter(|record| record.speed() < MOVING_SPEED)
+        .filter_map(|record| record.ecef().to_geo_on(ellipsoid).ok())
+        .map(|geo| geo.alt)
+        .collect();
+
+    (!altitudes.is_empty()).then(|| altitudes.iter().sum::<f64>() / altitudes.len() as f64)
+}
+
+// Index of the largest value in `values[start..end]`, skipping NaNs from flagged records
+fn argmax(values: &[f64], start: usize, end: usize) -> usize {
+    (start..end)
+        .filter(|i| !values[*i].is_nan())
+        .max_by(|a, b| values[*a].total_cmp(&values[*b]))
+        .unwrap_or(start)
+}
+
+/***************************
+           Tests
+***************************/
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Vertical flight on the equator at the prime meridian
+    fn record(seconds: u64, alt: f64, vel: f64) -> TelemetryRecord {
+        TelemetryRecord {
+            timestamp_ns: seconds * 1_000_000_000,
+            pos_x: 6_378_137.0 + alt,
+            vel_x: vel,
+            ..TelemetryRecord::default()
+        }
+    }
+
+    #[test]
+    fn detect_flight_events() {
+        let records = vec![
+            record(0, 0.0, 0.0),
+            record(1, 0.0, 0.0),
+            record(2, 10.0, 20.0),
+            record(3, 100.0, 150.0),
+            record(4, 400.0, 300.0),
+            record(5, 650.0, 200.0),
+            record(6, 750.0, 0.5),
+            record(7, 600.0, -150.0),
+            record(8, 200.0, -250.0),
+            record(9, 20.0, -30.0),
+            record(10, 0.0, 0.0),
+        ];
+
+        let actual: Vec<(EventKind, usize)> = detect_events(&records, &Ellipsoid::WGS84)
+            .iter()
+            .map(|event| (event.kind, event.index))
+            .collect();
+
+        assert_eq!(
+            vec![
+                (EventKind::Liftoff, 2),
+                (EventKind::MaxQ, 4),
+                (EventKind::Meco, 4),
+                (EventKind::Apogee, 6),
+                (EventKind::Landing, 10),
+            ],
+            actual
+        );
+    }
+
+    #[test]
+    fn pad_altitude_before_liftoff() {
+        let records = vec![
+            record(0, 1_000.0, 0.0),
+            record(1, 1_002.0, 0.5),
+            record(2, 1_010.0, 20.0),
+            record(3, 1_100.0, 150.0),
+            record(4, 1_000.0, 0.0),
+        ];
+        let events = detect_events(&records, &Ellipsoid::WGS84);
+
+        let actual = pad_altitude(&records, &events, &Ellipsoid::WGS84).unwrap();
+
+        assert!((1_001.0 - actual).abs() < 1e-6);
+        assert_eq!(None, pad_altitude(&records[2..4], &[], &Ellipsoid::WGS84));
+    }
+
+    #[test]
+    fn flight_phases() {
+        let events: Vec<FlightEvent> = [
+            (EventKind::Liftoff, 2),
+            (EventKind::MaxQ, 3),
+            (EventKind::Meco, 4),
+            (EventKind::Apogee, 6),
+            (EventKind::Landing, 10),
+        ]
+        .iter()
+        .map(|(kind, index)| FlightEvent {
+            kind: *kind,
+            index: *index,
+            timestamp_ns: *index as u64,
+        })
+        .collect();
+
+        let phases: Vec<FlightPhase> = [0, 2, 3, 5, 6, 9, 10, 11]
+            .iter()
+            .map(|timestamp_ns| FlightPhase::at(&events, *timestamp_ns))
+            .collect();
+
+        assert_eq!(
+            vec![
+                FlightPhase::Prelaunch,
+                FlightPhase::PoweredAscent,
+                FlightPhase::PoweredAscent,
+                FlightPhase::Coast,
+                FlightPhase::Descent,
+                FlightPhase::Descent,
+                FlightPhase::Landed,
+                FlightPhase::Landed,
+            ],
+            phases
+        );
+    }
+
+    #[test]
+    fn no_events_on_the_pad() {
+        let records = vec![record(0, 0.0, 0.0), record(1, 0.0, 0.1)];
+        assert!(detect_events(&records, &Ellipsoid::WGS84).is_empty());
+    }
+}
@@ -0,0 +1,274 @@
+//! ECEF to geodetic conversions behind a common trait, so they can be benchmarked
+//! and checked for accuracy against each other.
+
+use std::fmt;
+
+use crate::coord::{EcefCoord, Ellipsoid, GeoCoord, GeoError};
+
+// Altitudes of the accuracy sweep, from deep below the surface out to geostationary orbit
+const SWEEP_ALTITUDES: [f64; 11] = [
+    -1_000_000.0,
+    -100_000.0,
+    -10_000.0,
+    -1_000.0,
+    0.0,
+    1_000.0,
+    10_000.0,
+    100_000.0,
+    1_000_000.0,
+    10_000_000.0,
+    35_786_000.0,
+];
+
+pub trait GeodeticConversion {
+    fn name(&self) -> &'static str;
+    fn to_geo(&self, ecef: &EcefCoord, ellipsoid: &Ellipsoid) -> Result<GeoCoord, GeoError>;
+}
+
+/// Fixed point iteration on the latitude, `EcefCoord::to_geo`
+pub struct Iterative;
+/// Olson (1996), `EcefCoord::to_geo_olson`
+pub struct Olson;
+/// Bowring (1976), a single step from the parametric latitude
+pub struct Bowring;
+/// Heikkinen (1982), closed form from Ferrari's solution of the quartic
+pub struct Heikkinen;
+/// Zhu (1993), closed form with one Newton step on the quartic
+pub struct Zhu;
+
+pub const ALGORITHMS: [&dyn GeodeticConversion; 5] =
+    [&Iterative, &Olson, &Bowring, &Heikkinen, &Zhu];
+
+impl GeodeticConversion for Iterative {
+    fn name(&self) -> &'static str {
+        "Iterative"
+    }
+
+    fn to_geo(&self, ecef: &EcefCoord, ellipsoid: &Ellipsoid) -> Result<GeoCoord, GeoError> {
+        ecef.to_geo_on(ellipsoid)
+    }
+}
+
+impl GeodeticConversion for Olson {
+    fn name(&self) -> &'static str {
+        "Olson"
+    }
+
+    fn to_geo(&self, ecef: &EcefCoord, ellipsoid: &Ellipsoid) -> Result<GeoCoord, GeoError> {
+        ecef.to_geo_olson_on(ellipsoid)
+    }
+}
+
+impl GeodeticConversion for Bowring {
+    fn name(&self) -> &'static str {
+        "Bowring"
+    }
+
+    fn to_geo(&self, ecef: &EcefCoord, ellipsoid: &Ellipsoid) -> Result<GeoCoord, GeoError> {
+        ecef.check()?;
+        let (a, e_sq) = (ellipsoid.a, ellipsoid.e_sq());
+        let b = a * (1.0 - ellipsoid.f);
+        let ep_sq = e_sq / (1.0 - e_sq);
+        let p = ecef.x.hypot(ecef.y);
+
+        let (sin_beta, cos_beta) = (ecef.z * a).atan2(p * b).sin_cos();
+        let lat = (ecef.z + ep_sq * b * sin_beta.powi(3)).atan2(p - e_sq * a * cos_beta.powi(3));
+
+        Ok(geo(ecef, lat, ellipsoid.height(p, ecef.z, lat)))
+    }
+}
+
+impl GeodeticConversion for Heikkinen {
+    fn name(&self) -> &'static str {
+        "Heikkinen"
+    }
+
+    fn to_geo(&self, ecef: &EcefCoord, ellipsoid: &Ellipsoid) -> Result<GeoCoord, GeoError> {
+        ecef.check()?;
+        let (a, e_sq) = (ellipsoid.a, ellipsoid.e_sq());
+        let b = a * (1.0 - ellipsoid.f);
+        let ep_sq = e_sq / (1.0 - e_sq);
+        let (p, z) = (ecef.x.hypot(ecef.y), ecef.z);
+
+        let f = 54.0 * b * b * z * z;
+        let g = p * p + (1.0 - e_sq) * z * z - e_sq * (a * a - b * b);
+        let c = e_sq * e_sq * f * p * p / g.powi(3);
+        let s = (1.0 + c + (c * c + 2.0 * c).sqrt()).cbrt();
+        let k = s + 1.0 + 1.0 / s;
+        let big_p = f / (3.0 * k * k * g * g);
+        let q = (1.0 + 2.0 * e_sq * e_sq * big_p).sqrt();
+        let r0 = -big_p * e_sq * p / (1.0 + q)
+            + (0.5 * a * a * (1.0 + 1.0 / q)
+                - big_p * (1.0 - e_sq) * z * z / (q * (1.0 + q))
+                - 0.5 * big_p * p * p)
+                // Rounding takes this a hair below zero on the polar axis
+                .max(0.0)
+                .sqrt();
+        let u = (p - e_sq * r0).hypot(z);
+        let v = ((p - e_sq * r0).powi(2) + (1.0 - e_sq) * z * z).sqrt();
+        let z0 = b * b * z / (a * v);
+
+        let lat = (z + ep_sq * z0).atan2(p);
+        Ok(geo(ecef, lat, u * (1.0 - b * b / (a * v))))
+    }
+}
+
+impl GeodeticConversion for Zhu {
+    fn name(&self) -> &'static str {
+        "Zhu"
+    }
+
+    fn to_geo(&self, ecef: &EcefCoord, ellipsoid: &Ellipsoid) -> Result<GeoCoord, GeoError> {
+        ecef.check()?;
+        let (a, e_sq) = (ellipsoid.a, ellipsoid.e_sq());
+        let w = ecef.x.hypot(ecef.y);
+        let z = ecef.z;
+
+        let l = e_sq / 2.0;
+        let l2 = l * l;
+        let m = (w / a).powi(2);
+        let n = (1.0 - e_sq) * (z / a).powi(2);
+        let p = (m + n - 4.0 * l2) / 6.0;
+        let g = m * n * l2;
+        let h = 2.0 * p.powi(3) + g;
+        let c = (h + g + 2.0 * (h * g).sqrt()).cbrt() / 2f64.cbrt();
+        let i = -(2.0 * l2 + m + n) / 2.0;
+        let beta = i / 3.0 - c - p * p / c;
+        let k = l2 * (l2 - m - n);
+        let t = ((beta * beta - k).sqrt() - (beta + i) / 2.0).sqrt()
+            - (m - n).signum() * ((beta - i) / 2.0).abs().sqrt();
+
+        // One Newton step on the quartic polishes the root
+        let quartic = t.powi(4) + 2.0 * i * t * t + 2.0 * l * (m - n) * t + k;
+        let slope = 4.0 * t.powi(3) + 4.0 * i * t + 2.0 * l * (m - n);
+        let t = t - quartic / slope;
+        let u = t + l;
+        let v = t - l;
+
+        let lat = (z * u).atan2(w * v);
+        let dw = w * (1.0 - 1.0 / u);
+        let dz = z * (1.0 - (1.0 - e_sq) / v);
+        Ok(geo(ecef, lat, dw.hypot(dz).copysign(u - 1.0)))
+    }
+}
+
+fn geo(ecef: &EcefCoord, lat: f64, alt: f64) -> GeoCoord {
+    GeoCoord {
+        lat: lat.to_degrees(),
+        lon: ecef.y.atan2(ecef.x).to_degrees(),
+        alt,
+    }
+}
+
+/// Largest errors of a conversion over the sweep
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Accuracy {
+    pub name: &'static str,
+    /// In degrees
+    pub max_lat_error: f64,
+    /// In meters
+    pub max_alt_error: f64,
+}
+
+impl fmt::Display for Accuracy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:<10} max lat error {:.3e} deg, max alt error {:.3e} m",
+            self.name, self.max_lat_error, self.max_alt_error
+        )
+    }
+}
+
+/// Convert a grid of points with known geodetic coordinates, every half degree of latitude pole
+/// to pole at altitudes from -1000 km to GEO, and compare with where they were made from.
+/// A conversion that fails or gives a non-finite result has an infinite error.
+pub fn accuracy_sweep(conversion: &dyn GeodeticConversion, ellipsoid: &Ellipsoid) -> Accuracy {
+    let mut accuracy = Accuracy {
+        name: conversion.name(),
+        max_lat_error: 0.0,
+        max_alt_error: 0.0,
+    };
+    let error = |expected: f64, actual: f64| {
+        if actual.is_finite() {
+            (expected - actual).abs()
+        } else {
+            f64::INFINITY
+        }
+    };
+
+    for alt in SWEEP_ALTITUDES {
+        for lat in (-180..=180).map(|half_degrees| f64::from(half_degrees) / 2.0) {
+            let expected = GeoCoord {
+                lat,
+                lon: 45.0,
+                alt,
+            };
+            let (lat_error, alt_error) =
+                match conversion.to_geo(&expected.to_ecef_on(ellipsoid), ellipsoid) {
+                    Ok(actual) => (error(lat, actual.lat), error(alt, actual.alt)),
+                    Err(_) => (f64::INFINITY, f64::INFINITY),
+                };
+            accuracy.max_lat_error = accuracy.max_lat_error.max(lat_error);
+            accuracy.max_alt_error = accuracy.max_alt_error.max(alt_error);
+        }
+    }
+
+    accuracy
+}
+
+/***************************
+           Tests
+***************************/
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case(&Iterative, 1e-9, 1e-6)]
+    #[case(&Olson, 1e-9, 1e-6)]
+    #[case(&Heikkinen, 1e-9, 1e-6)]
+    #[case(&Zhu, 1e-9, 1e-6)]
+    // A single step leaves centimeters of latitude error far from the surface
+    #[case(&Bowring, 1e-6, 1e-6)]
+    fn accuracy_sweep_test(
+        #[case] conversion: &dyn GeodeticConversion,
+        #[case] max_lat_error: f64,
+        #[case] max_alt_error: f64,
+    ) {
+        let accuracy = accuracy_sweep(conversion, &Ellipsoid::WGS84);
+
+        assert!(accuracy.max_lat_error < max_lat_error, "{accuracy}");
+        assert!(accuracy.max_alt_error < max_alt_error, "{accuracy}");
+    }
+
+    #[test]
+    fn all_agree_on_the_surface() {
+        let expected = GeoCoord {
+            lat: 31.4,
+            lon: -104.8,
+            alt: 1_130.0,
+        };
+        let ecef = expected.to_ecef_on(&Ellipsoid::WGS84);
+
+        for conversion in ALGORITHMS {
+            let actual = conversion.to_geo(&ecef, &Ellipsoid::WGS84).unwrap();
+            assert!(
+                (expected.lat - actual.lat).abs() < 1e-9,
+                "{}",
+                conversion.name()
+            );
+            assert!(
+                (expected.lon - actual.lon).abs() < 1e-9,
+                "{}",
+                conversion.name()
+            );
+            assert!(
+                (expected.alt - actual.alt).abs() < 1e-6,
+                "{}",
+                conversion.name()
+            );
+        }
+    }
+}
@@ -0,0 +1,150 @@
+use std::error::Error;
+use std::fmt;
+use std::io::Read;
+
+use crate::input::open_input;
+
+/// Geoid undulation grid, the height of mean sea level above the ellipsoid
+#[derive(Debug, Default, Clone)]
+pub struct Geoid {
+    north: f64,
+    west: f64,
+    /// Grid spacing in degrees
+    dlat: f64,
+    dlon: f64,
+    rows: usize,
+    cols: usize,
+    /// Heights in meters, row by row from the north edge and west to east along each row
+    heights: Vec<f64>,
+}
+
+#[derive(Debug)]
+pub struct GeoidFormatError(String);
+
+impl fmt::Display for GeoidFormatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid geoid grid: {}", self.0)
+    }
+}
+
+impl Error for GeoidFormatError {}
+
+impl Geoid {
+    /// Read a grid in the format of the EGM96 `WW15MGH.GRD` file, optionally compressed
+    pub fn from_file(file_path: &str) -> Result<Self, Box<dyn Error>> {
+        let mut text = String::new();
+        open_input(file_path)?.read_to_string(&mut text)?;
+        Ok(Self::parse(&text)?)
+    }
+
+    /// A header of the south, north, west and east edges and the latitude and longitude
+    /// spacing in degrees, followed by the heights in meters from the north-west corner,
+    /// west to east then north to south. Line breaks are not significant.
+    #[allow(clippy::cast_possible_truncation)]
+    #[allow(clippy::cast_sign_loss)]
+    pub fn parse(text: &str) -> Result<Self, GeoidFormatError> {
+        let values: Vec<f64> = text
+            .split_whitespace()
+            .map(|value| {
+                value
+                    .parse()
+                    .map_err(|_| GeoidFormatError(format!("{value} is not a number")))
+            })
+            .collect::<Result<_, _>>()?;
+        let [south, north, west, east, dlat, dlon] = values
+            .get(..6)
+            .and_then(|header| header.try_into().ok())
+            .ok_or_else(|| GeoidFormatError("missing header".to_string()))?;
+        if dlat <= 0.0 || dlon <= 0.0 || north <= south || east <= west {
+            return Err(GeoidFormatError(format!(
+                "bad extent {south} to {north}, {west} to {east} by {dlat}, {dlon}"
+            )));
+        }
+
+        let rows = ((north - south) / dlat).round() as usize + 1;
+        let cols = ((east - west) / dlon).round() as usize + 1;
+        let heights = values[6..].to_vec();
+        if heights.len() != rows * cols {
+            return Err(GeoidFormatError(format!(
+                "expected {rows} x {cols} heights but found {}",
+                heights.len()
+            )));
+        }
+
+        Ok(Geoid {
+            north,
+            west,
+            dlat,
+            dlon,
+            rows,
+            cols,
+            heights,
+        })
+    }
+
+    /// Height of the geoid above the ellipsoid in meters at `lat`, `lon` degrees, bilinear
+    /// between the grid points. Longitudes wrap around and latitudes are clamped to the grid.
+    #[allow(clippy::cast_possible_truncation)]
+    #[allow(clippy::cast_sign_loss)]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn undulation(&self, lat: f64, lon: f64) -> f64 {
+        let row = ((self.north - lat) / self.dlat).clamp(0.0, (self.rows - 1) as f64);
+        let col = (lon - self.west).rem_euclid(360.0) / self.dlon;
+
+        let (row0, col0) = (row.floor() as usize, col.floor() as usize);
+        let row1 = (row0 + 1).min(self.rows - 1);
+        // Global grids repeat the first column at the end, regional ones stop at their edge
+        let col0 = col0.min(self.cols - 1);
+        let col1 = (col0 + 1).min(self.cols - 1);
+        let (t, u) = (row - row0 as f64, col - col0 as f64);
+        let u = u.clamp(0.0, 1.0);
+
+        let height = |row: usize, col: usize| self.heights[row * self.cols + col];
+        let north = height(row0, col0) * (1.0 - u) + height(row0, col1) * u;
+        let south = height(row1, col0) * (1.0 - u) + height(row1, col1) * u;
+        north * (1.0 - t) + south * t
+    }
+}
+
+/***************************
+           Tests
+***************************/
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use float_cmp::approx_eq;
+    use rstest::rstest;
+
+    // Global grid at 90 degree spacing, rows from the north pole
+    const GRID: &str = "-90 90 0 360 90 90
+        10 10 10 10 10
+        0 20 40 60 0
+        -10 -10 -10 -10 -10";
+
+    #[rstest]
+    #[case(90.0, 45.0, 10.0)]
+    #[case(0.0, 90.0, 20.0)]
+    #[case(0.0, 45.0, 10.0)]
+    #[case(45.0, 135.0, 20.0)]
+    #[case(0.0, -90.0, 60.0)]
+    #[case(0.0, 315.0, 30.0)]
+    #[case(-45.0, 0.0, -5.0)]
+    fn undulation_test(#[case] lat: f64, #[case] lon: f64, #[case] expected: f64) {
+        let geoid = Geoid::parse(GRID).unwrap();
+        assert!(approx_eq!(
+            f64,
+            expected,
+            geoid.undulation(lat, lon),
+            epsilon = 1e-9
+        ));
+    }
+
+    #[rstest]
+    #[case("-90 90 0 360")]
+    #[case("-90 90 0 360 90 90 1 2 3")]
+    #[case("-90 90 0 360 90 90 10 ten")]
+    #[case("90 -90 0 360 90 90")]
+    fn bad_grid(#[case] text: &str) {
+        assert!(Geoid::parse(text).is_err());
+    }
+}
@@ -0,0 +1,72 @@
+//! Gravity of the rotating Earth in ECEF, shared by the trajectory propagators.
+
+use blue_telemetry_tui::coord::Ellipsoid;
+
+const A: f64 = Ellipsoid::WGS84.a;
+
+/// Earth's gravitational parameter in m^3/s^2
+pub const GM: f64 = 3.986_004_418e14;
+/// Second zonal harmonic of the geopotential, the Earth's oblateness
+pub const J2: f64 = 1.082_626_68e-3;
+/// Rotation rate of the Earth in rad/s
+pub const EARTH_RATE: f64 = 7.292_115e-5;
+
+/// Acceleration of a free body at `pos` moving at `vel` in ECEF: J2 gravitation
+/// plus the centrifugal and Coriolis terms of the rotating frame
+pub fn acceleration(pos: &[f64; 3], vel: &[f64; 3]) -> [f64; 3] {
+    let [x, y, z] = *pos;
+    let r2 = x * x + y * y + z * z;
+    let r = r2.sqrt();
+    let k = -GM / (r2 * r);
+    let j2 = 1.5 * J2 * A * A / r2;
+    let z2 = 5.0 * z * z / r2;
+
+    let gravitation = [
+        k * x * (1.0 + j2 * (1.0 - z2)),
+        k * y * (1.0 + j2 * (1.0 - z2)),
+        k * z * (1.0 + j2 * (3.0 - z2)),
+    ];
+    // -w x (w x r) with w along z
+    let centrifugal = [
+        EARTH_RATE * EARTH_RATE * x,
+        EARTH_RATE * EARTH_RATE * y,
+        0.0,
+    ];
+    // -2 w x v
+    let coriolis = [2.0 * EARTH_RATE * vel[1], -2.0 * EARTH_RATE * vel[0], 0.0];
+
+    std::array::from_fn(|i| gravitation[i] + centrifugal[i] + coriolis[i])
+}
+
+/***************************
+           Tests
+***************************/
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use float_cmp::approx_eq;
+    use rstest::rstest;
+
+    // Normal gravity is 9.7803 m/s^2 on the equator and 9.8322 m/s^2 at the poles
+    #[rstest]
+    #[case([A, 0.0, 0.0], [-9.780_3, 0.0, 0.0])]
+    #[case([0.0, 0.0, 6_356_752.3], [0.0, 0.0, -9.832_2])]
+    fn gravity_at_rest(#[case] pos: [f64; 3], #[case] expected: [f64; 3]) {
+        let actual = acceleration(&pos, &[0.0; 3]);
+        for (expected, actual) in expected.iter().zip(actual) {
+            assert!(approx_eq!(f64, *expected, actual, epsilon = 1e-3));
+        }
+    }
+
+    #[test]
+    fn coriolis_deflects_east_to_up() {
+        let at_rest = acceleration(&[A, 0.0, 0.0], &[0.0; 3]);
+        let moving_east = acceleration(&[A, 0.0, 0.0], &[0.0, 100.0, 0.0]);
+        assert!(approx_eq!(
+            f64,
+            2.0 * EARTH_RATE * 100.0,
+            moving_east[0] - at_rest[0],
+            epsilon = 1e-12
+        ));
+    }
+}
@@ -0,0 +1,243 @@
+//! Universal Transverse Mercator and Military Grid Reference System positions, the grids
+//! recovery teams navigate with. The projection uses Krüger's series to the fourth order in
+//! the third flattening, good to well under a millimeter within a zone.
+
+use std::fmt;
+
+use crate::coord::{Ellipsoid, GeoCoord};
+
+// Scale on the central meridian and the false easting and southern false northing in meters
+const SCALE: f64 = 0.9996;
+const FALSE_EASTING: f64 = 500_000.0;
+const FALSE_NORTHING: f64 = 10_000_000.0;
+// UTM stops short of the poles, which use the polar stereographic grids instead
+const MIN_LAT: f64 = -80.0;
+const MAX_LAT: f64 = 84.0;
+// Latitude bands of 8 degrees from 80 S, band X stretches to 84 N
+const BANDS: &[u8] = b"CDEFGHJKLMNPQRSTUVWX";
+// MGRS 100 km square letters, I and O are left out
+const COLUMN_LETTERS: &[u8] = b"ABCDEFGHJKLMNPQRSTUVWXYZ";
+const ROW_LETTERS: &[u8] = b"ABCDEFGHJKLMNPQRSTUV";
+const SQUARE: f64 = 100_000.0;
+
+/// Position in a UTM zone, in meters
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Utm {
+    pub zone: u8,
+    /// Latitude band letter, N and above are the northern hemisphere
+    pub band: char,
+    pub easting: f64,
+    pub northing: f64,
+}
+
+/// MGRS grid reference, a UTM position within a lettered 100 km square
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Mgrs {
+    pub zone: u8,
+    pub band: char,
+    /// Column and row letters of the 100 km square
+    pub square: [char; 2],
+    /// Meters east and north of the square's south-west corner
+    pub easting: f64,
+    pub northing: f64,
+}
+
+impl fmt::Display for Utm {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}{} {:.0}E {:.0}N",
+            self.zone, self.band, self.easting, self.northing
+        )
+    }
+}
+
+impl fmt::Display for Mgrs {
+    /// One meter references, the digits are truncated as the grid convention is to
+    /// name the square a position is in
+    #[allow(clippy::cast_possible_truncation)]
+    #[allow(clippy::cast_sign_loss)]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}{} {}{} {:05} {:05}",
+            self.zone,
+            self.band,
+            self.square[0],
+            self.square[1],
+            self.easting as u32,
+            self.northing as u32
+        )
+    }
+}
+
+impl GeoCoord {
+    /// Project onto the UTM zone the position falls in, including the wider zones around
+    /// Norway and Svalbard. None outside of 80 S to 84 N.
+    #[allow(clippy::cast_possible_truncation)]
+    #[allow(clippy::cast_sign_loss)]
+    pub fn to_utm(&self, ellipsoid: &Ellipsoid) -> Option<Utm> {
+        if !(MIN_LAT..=MAX_LAT).contains(&self.lat) || !self.lon.is_finite() {
+            return None;
+        }
+        let lon = (self.lon + 180.0).rem_euclid(360.0) - 180.0;
+        let zone = utm_zone(self.lat, lon);
+        let band = BANDS[(((self.lat - MIN_LAT) / 8.0) as usize).min(BANDS.len() - 1)] as char;
+        let central_meridian = f64::from(zone) * 6.0 - 183.0;
+
+        let (x, y) = transverse_mercator(self.lat, lon - central_meridian, ellipsoid);
+        let northing = if self.lat < 0.0 {
+            y + FALSE_NORTHING
+        } else {
+            y
+        };
+        Some(Utm {
+            zone,
+            band,
+            easting: x + FALSE_EASTING,
+            northing,
+        })
+    }
+
+    /// MGRS grid reference of the position, see `to_utm`
+    pub fn to_mgrs(&self, ellipsoid: &Ellipsoid) -> Option<Mgrs> {
+        self.to_utm(ellipsoid).map(|utm| utm.to_mgrs())
+    }
+}
+
+impl Utm {
+    /// Name the 100 km square with the lettering used on WGS84. Columns restart every
+    /// third zone and the rows of even zones are shifted by five letters.
+    #[allow(clippy::cast_possible_truncation)]
+    #[allow(clippy::cast_sign_loss)]
+    pub fn to_mgrs(&self) -> Mgrs {
+        let set = usize::from(self.zone - 1) % 3;
+        let column = (self.easting / SQUARE) as usize;
+        let row = (self.northing / SQUARE) as usize;
+        let row_offset = if self.zone.is_multiple_of(2) { 5 } else { 0 };
+        let column_letter = COLUMN_LETTERS[(set * 8 + column - 1) % COLUMN_LETTERS.len()];
+        let row_letter = ROW_LETTERS[(row + row_offset) % ROW_LETTERS.len()];
+
+        Mgrs {
+            zone: self.zone,
+            band: self.band,
+            square: [column_letter as char, row_letter as char],
+            easting: self.easting.rem_euclid(SQUARE),
+            northing: self.northing.rem_euclid(SQUARE),
+        }
+    }
+}
+
+// Zones are 6 degrees wide from 180 W, apart from the exceptions over Scandinavia
+#[allow(clippy::cast_possible_truncation)]
+#[allow(clippy::cast_sign_loss)]
+fn utm_zone(lat: f64, lon: f64) -> u8 {
+    if (56.0..64.0).contains(&lat) && (3.0..12.0).contains(&lon) {
+        return 32;
+    }
+    if lat >= 72.0 && (0.0..42.0).contains(&lon) {
+        return match lon {
+            lon if lon < 9.0 => 31,
+            lon if lon < 21.0 => 33,
+            lon if lon < 33.0 => 35,
+            _ => 37,
+        };
+    }
+    (((lon + 180.0) / 6.0) as u8 + 1).min(60)
+}
+
+// Scaled easting and northing in meters of `lat` degrees, `dlon` degrees from the central meridian
+fn transverse_mercator(lat: f64, dlon: f64, ellipsoid: &Ellipsoid) -> (f64, f64) {
+    let n = ellipsoid.f / (2.0 - ellipsoid.f);
+    let (n2, n3, n4) = (n * n, n * n * n, n * n * n * n);
+    // Radius of the circle with the circumference of a meridian
+    let radius = ellipsoid.a / (1.0 + n) * (1.0 + n2 / 4.0 + n4 / 64.0);
+    let alpha = [
+        n / 2.0 - 2.0 * n2 / 3.0 + 5.0 * n3 / 16.0 + 41.0 * n4 / 180.0,
+        13.0 * n2 / 48.0 - 3.0 * n3 / 5.0 + 557.0 * n4 / 1440.0,
+        61.0 * n3 / 240.0 - 103.0 * n4 / 140.0,
+        49_561.0 * n4 / 161_280.0,
+    ];
+
+    // Conformal latitude, then the spherical transverse Mercator
+    let e = ellipsoid.e_sq().sqrt();
+    let sin_lat = lat.to_radians().sin();
+    let t = (sin_lat.atanh() - e * (e * sin_lat).atanh()).sinh();
+    let dlon = dlon.to_radians();
+    let xi = t.atan2(dlon.cos());
+    let eta = (dlon.sin() / (1.0 + t * t).sqrt()).atanh();
+
+    let (mut x, mut y) = (eta, xi);
+    for (j, alpha) in (1..).zip(alpha) {
+        let k = 2.0 * f64::from(j);
+        x += alpha * (k * xi).cos() * (k * eta).sinh();
+        y += alpha * (k * xi).sin() * (k * eta).cosh();
+    }
+    (SCALE * radius * x, SCALE * radius * y)
+}
+
+/***************************
+           Tests
+***************************/
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use float_cmp::approx_eq;
+    use rstest::rstest;
+
+    fn geo(lat: f64, lon: f64) -> GeoCoord {
+        GeoCoord { lat, lon, alt: 0.0 }
+    }
+
+    #[rstest]
+    // On the equator and central meridian of zone 31
+    #[case(0.0, 3.0, 31, 'N', 500_000.0, 0.0)]
+    // Scaled meridian distance to 45 degrees
+    #[case(45.0, 3.0, 31, 'T', 500_000.0, 4_982_950.400)]
+    #[case(-45.0, 3.0, 31, 'G', 500_000.0, 5_017_049.600)]
+    // Washington Monument and the Sydney Opera House
+    #[case(38.8895, -77.0352, 18, 'S', 323_486.737, 4_306_483.048)]
+    #[case(-33.8568, 151.2153, 56, 'H', 334_900.570, 6_252_288.753)]
+    fn utm_test(
+        #[case] lat: f64,
+        #[case] lon: f64,
+        #[case] zone: u8,
+        #[case] band: char,
+        #[case] easting: f64,
+        #[case] northing: f64,
+    ) {
+        let actual = geo(lat, lon).to_utm(&Ellipsoid::WGS84).unwrap();
+
+        assert_eq!((zone, band), (actual.zone, actual.band));
+        assert!(approx_eq!(f64, easting, actual.easting, epsilon = 1e-3));
+        assert!(approx_eq!(f64, northing, actual.northing, epsilon = 1e-3));
+    }
+
+    #[rstest]
+    #[case(60.0, 5.0, 32)]
+    #[case(60.0, 2.9, 31)]
+    #[case(78.0, 8.0, 31)]
+    #[case(78.0, 10.0, 33)]
+    #[case(78.0, 40.0, 37)]
+    #[case(0.0, 179.9, 60)]
+    #[case(0.0, -180.0, 1)]
+    fn zone_test(#[case] lat: f64, #[case] lon: f64, #[case] zone: u8) {
+        assert_eq!(zone, geo(lat, lon).to_utm(&Ellipsoid::WGS84).unwrap().zone);
+    }
+
+    #[test]
+    fn outside_utm() {
+        assert_eq!(None, geo(85.0, 0.0).to_utm(&Ellipsoid::WGS84));
+        assert_eq!(None, geo(-81.0, 0.0).to_mgrs(&Ellipsoid::WGS84));
+        assert_eq!(None, geo(f64::NAN, 0.0).to_utm(&Ellipsoid::WGS84));
+    }
+
+    #[rstest]
+    #[case(38.8895, -77.0352, "18S UJ 23486 06483")]
+    #[case(-33.8568, 151.2153, "56H LH 34900 52288")]
+    #[case(0.0, 3.0, "31N EA 00000 00000")]
+    fn mgrs_test(#[case] lat: f64, #[case] lon: f64, #[case] expected: &str) {
+        let actual = geo(lat, lon).to_mgrs(&Ellipsoid::WGS84).unwrap();
+        assert_eq!(expected, actual.to_string());
+    }
+}
@@ -0,0 +1,94 @@
+use flate2::read::MultiGzDecoder;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read};
+use std::path::Path;
+
+const GZIP_MAGIC: [u8; 2] = [0x1F, 0x8B];
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xB5, 0x2F, 0xFD];
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Compression {
+    None,
+    Gzip,
+    Zstd,
+}
+
+impl Compression {
+    /// Detect compression from the leading bytes, falling back to the file extension
+    pub fn detect(header: &[u8], file_path: &str) -> Self {
+        if header.starts_with(&GZIP_MAGIC) {
+            return Compression::Gzip;
+        }
+        if header.starts_with(&ZSTD_MAGIC) {
+            return Compression::Zstd;
+        }
+
+        match Path::new(file_path)
+            .extension()
+            .and_then(|ext| ext.to_str())
+        {
+            Some("gz") => Compression::Gzip,
+            Some("zst") => Compression::Zstd,
+            _ => Compression::None,
+        }
+    }
+}
+
+/// Open a file for reading, decompressing gzip and zstd on the fly
+pub fn open_input(file_path: &str) -> io::Result<Box<dyn Read>> {
+    let file = File::open(file_path)?;
+    decompress(BufReader::new(file), file_path)
+}
+
+pub fn decompress<R: BufRead + 'static>(
+    mut reader: R,
+    file_path: &str,
+) -> io::Result<Box<dyn Read>> {
+    // Peek at the magic bytes without consuming them
+    let header = reader.fill_buf()?;
+
+    Ok(match Compression::detect(header, file_path) {
+        Compression::None => Box::new(reader),
+        // Concatenated gzip members are valid, so decode all of them
+        Compression::Gzip => Box::new(MultiGzDecoder::new(reader)),
+        Compression::Zstd => Box::new(zstd::stream::read::Decoder::with_buffer(reader)?),
+    })
+}
+
+/***************************
+           Tests
+***************************/
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case(&[0x1F, 0x8B, 0x08], "data.csv", Compression::Gzip)]
+    #[case(&[0x28, 0xB5, 0x2F, 0xFD], "data.csv", Compression::Zstd)]
+    #[case(b"TIME", "data.csv", Compression::None)]
+    #[case(b"TIME", "data.csv.gz", Compression::Gzip)]
+    #[case(b"TIME", "data.csv.zst", Compression::Zstd)]
+    fn detect_compression(
+        #[case] header: &[u8],
+        #[case] file_path: &str,
+        #[case] expected: Compression,
+    ) {
+        assert_eq!(expected, Compression::detect(header, file_path));
+    }
+
+    #[rstest]
+    #[case("data/test.csv")]
+    #[case("data/test.csv.gz")]
+    #[case("data/test.csv.zst")]
+    fn open_compressed_input(#[case] file_path: &str) {
+        let mut actual = String::new();
+        open_input(file_path)
+            .unwrap()
+            .read_to_string(&mut actual)
+            .unwrap();
+
+        let expected = std::fs::read_to_string("data/test.csv").unwrap();
+        assert_eq!(expected, actual);
+    }
+}
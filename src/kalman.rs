@@ -0,0 +1,354 @@
+use crate::csv_reader::TelemetryRecord;
+
+type Mat3 = [[f64; 3]; 3];
+
+// Variance of the acceleration before the filter has seen any, in (m/s^2)^2
+const INITIAL_ACCEL_VARIANCE: f64 = 100.0;
+
+/// Noise levels of the constant acceleration model and the measurements
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct KalmanConfig {
+    /// Spectral density of the white jerk driving the model, in m^2/s^5
+    pub process_noise: f64,
+    /// Standard deviation of a position measurement in meters
+    pub pos_noise: f64,
+    /// Standard deviation of a velocity measurement in m/s
+    pub vel_noise: f64,
+}
+
+impl Default for KalmanConfig {
+    fn default() -> Self {
+        KalmanConfig {
+            process_noise: 1.0,
+            pos_noise: 5.0,
+            vel_noise: 0.5,
+        }
+    }
+}
+
+/// Filtered state at one record. The ECEF axes are filtered independently,
+/// so each axis has its own position, velocity, acceleration state and covariance
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Estimate {
+    pub timestamp_ns: u64,
+    /// Position, velocity and acceleration per ECEF axis
+    pub state: [[f64; 3]; 3],
+    pub covariance: [Mat3; 3],
+}
+
+impl Estimate {
+    pub fn pos(&self) -> [f64; 3] {
+        self.state.map(|axis| axis[0])
+    }
+
+    pub fn vel(&self) -> [f64; 3] {
+        self.state.map(|axis| axis[1])
+    }
+
+    /// One sigma of the 3D position error in meters
+    pub fn pos_sigma(&self) -> f64 {
+        self.covariance.iter().map(|p| p[0][0]).sum::<f64>().sqrt()
+    }
+
+    pub fn to_record(self) -> TelemetryRecord {
+        let [pos_x, pos_y, pos_z] = self.pos();
+        let [vel_x, vel_y, vel_z] = self.vel();
+        TelemetryRecord {
+            timestamp_ns: self.timestamp_ns,
+            pos_x,
+            pos_y,
+            pos_z,
+            vel_x,
+            vel_y,
+            vel_z,
+            ..TelemetryRecord::default()
+        }
+    }
+}
+
+/// Run a constant acceleration Kalman filter forward over the records, using their
+/// positions and velocities as measurements. Records that are flagged or not finite
+/// only advance the prediction.
+#[allow(clippy::cast_precision_loss)]
+pub fn filter(records: &[TelemetryRecord], config: &KalmanConfig) -> Vec<Estimate> {
+    let mut estimates: Vec<Estimate> = Vec::with_capacity(records.len());
+    let r = [config.pos_noise.powi(2), config.vel_noise.powi(2)];
+
+    for record in records {
+        let measurement = measurement(record);
+
+        let Some(previous) = estimates.last() else {
+            // Start from the first usable measurement with no acceleration
+            let Some(z) = measurement else {
+                continue;
+            };
+            let p0 = [
+                [r[0], 0.0, 0.0],
+                [0.0, r[1], 0.0],
+                [0.0, 0.0, INITIAL_ACCEL_VARIANCE],
+            ];
+            estimates.push(Estimate {
+                timestamp_ns: record.timestamp_ns,
+                state: z.map(|[pos, vel]| [pos, vel, 0.0]),
+                covariance: [p0; 3],
+            });
+            continue;
+        };
+
+        let dt = record.timestamp_ns.saturating_sub(previous.timestamp_ns) as f64 / 1e9;
+        let (f, q) = (transition(dt), process_noise(dt, config.process_noise));
+        let mut estimate = Estimate {
+            timestamp_ns: record.timestamp_ns,
+            ..*previous
+        };
+
+        for axis in 0..3 {
+            let mut x = mat_vec(&f, &previous.state[axis]);
+            let mut p = add(
+                &mat_mul(&mat_mul(&f, &previous.covariance[axis]), &transpose(&f)),
+                &q,
+            );
+
+            if let Some(z) = measurement {
+                // The measurement picks out position and velocity, so H P H' is the top left of P
+                let s = [[p[0][0] + r[0], p[0][1]], [p[1][0], p[1][1] + r[1]]];
+                let det = s[0][0] * s[1][1] - s[0][1] * s[1][0];
+                let s_inv = [
+                    [s[1][1] / det, -s[0][1] / det],
+                    [-s[1][0] / det, s[0][0] / det],
+                ];
+                let gain: [[f64; 2]; 3] = std::array::from_fn(|i| {
+                    std::array::from_fn(|j| p[i][0] * s_inv[0][j] + p[i][1] * s_inv[1][j])
+                });
+
+                let innovation = [z[axis][0] - x[0], z[axis][1] - x[1]];
+                for i in 0..3 {
+                    x[i] += gain[i][0] * innovation[0] + gain[i][1] * innovation[1];
+                }
+                let hp = [p[0], p[1]];
+                p = std::array::from_fn(|i| {
+                    std::array::from_fn(|j| p[i][j] - gain[i][0] * hp[0][j] - gain[i][1] * hp[1][j])
+                });
+            }
+
+            estimate.state[axis] = x;
+            estimate.covariance[axis] = symmetric(&p);
+        }
+        estimates.push(estimate);
+    }
+
+    estimates
+}
+
+/// Filter forward then run a Rauch-Tung-Striebel smoother backward, so every
+/// estimate uses the measurements after it as well as before. Also returns how many
+/// estimates kept their filtered value on some axis because the predicted covariance
+/// couldn't be inverted.
+#[allow(clippy::cast_precision_loss)]
+pub fn smooth(records: &[TelemetryRecord], config: &KalmanConfig) -> (Vec<Estimate>, usize) {
+    let mut estimates = filter(records, config);
+    let mut skipped = 0;
+
+    for k in (0..estimates.len().saturating_sub(1)).rev() {
+        let (current, next) = (estimates[k], estimates[k + 1]);
+        let dt = next.timestamp_ns.saturating_sub(current.timestamp_ns) as f64 / 1e9;
+        let (f, q) = (transition(dt), process_noise(dt, config.process_noise));
+        let mut skipped_step = false;
+
+        for axis in 0..3 {
+            let x = current.state[axis];
+            let p = current.covariance[axis];
+            let x_pred = mat_vec(&f, &x);
+            let p_pred = add(&mat_mul(&mat_mul(&f, &p), &transpose(&f)), &q);
+            let Some(p_pred_inv) = inverse(&p_pred) else {
+                skipped_step = true;
+                continue;
+            };
+            let gain = mat_mul(&mat_mul(&p, &transpose(&f)), &p_pred_inv);
+
+            let correction: [f64; 3] = std::array::from_fn(|i| next.state[axis][i] - x_pred[i]);
+            let x_gain = mat_vec(&gain, &correction);
+            let p_diff: Mat3 = std::array::from_fn(|i| {
+                std::array::from_fn(|j| next.covariance[axis][i][j] - p_pred[i][j])
+            });
+            let p_gain = mat_mul(&mat_mul(&gain, &p_diff), &transpose(&gain));
+
+            estimates[k].state[axis] = std::array::from_fn(|i| x[i] + x_gain[i]);
+            estimates[k].covariance[axis] = symmetric(&add(&p, &p_gain));
+        }
+        skipped += usize::from(skipped_step);
+    }
+
+    (estimates, skipped)
+}
+
+/// Position and velocity per axis, if the record can be used as a measurement
+fn measurement(record: &TelemetryRecord) -> Option<[[f64; 2]; 3]> {
+    let z = [
+        [record.pos_x, record.vel_x],
+        [record.pos_y, record.vel_y],
+        [record.pos_z, record.vel_z],
+    ];
+    let usable = record.flags.is_empty() && z.iter().flatten().all(|value| value.is_finite());
+    usable.then_some(z)
+}
+
+fn transition(dt: f64) -> Mat3 {
+    [[1.0, dt, 0.5 * dt * dt], [0.0, 1.0, dt], [0.0, 0.0, 1.0]]
+}
+
+/// Process noise of white jerk with spectral density `q` integrated over `dt`
+fn process_noise(dt: f64, q: f64) -> Mat3 {
+    let (dt2, dt3) = (dt * dt, dt * dt * dt);
+    [
+        [dt3 * dt2 / 20.0, dt2 * dt2 / 8.0, dt3 / 6.0],
+        [dt2 * dt2 / 8.0, dt3 / 3.0, dt2 / 2.0],
+        [dt3 / 6.0, dt2 / 2.0, dt],
+    ]
+    .map(|row| row.map(|value| value * q))
+}
+
+fn mat_mul(a: &Mat3, b: &Mat3) -> Mat3 {
+    std::array::from_fn(|i| std::array::from_fn(|j| (0..3).map(|k| a[i][k] * b[k][j]).sum()))
+}
+
+fn mat_vec(a: &Mat3, v: &[f64; 3]) -> [f64; 3] {
+    std::array::from_fn(|i| (0..3).map(|k| a[i][k] * v[k]).sum())
+}
+
+fn transpose(a: &Mat3) -> Mat3 {
+    std::array::from_fn(|i| std::array::from_fn(|j| a[j][i]))
+}
+
+fn add(a: &Mat3, b: &Mat3) -> Mat3 {
+    std::array::from_fn(|i| std::array::from_fn(|j| a[i][j] + b[i][j]))
+}
+
+fn symmetric(a: &Mat3) -> Mat3 {
+    std::array::from_fn(|i| std::array::from_fn(|j| 0.5 * (a[i][j] + a[j][i])))
+}
+
+/// None if the matrix is singular to working precision, judged against the size of its
+/// entries so that covariances of any scale are treated alike
+fn inverse(a: &Mat3) -> Option<Mat3> {
+    let cofactor = |i: usize, j: usize| {
+        let (r0, r1) = ((i + 1) % 3, (i + 2) % 3);
+        let (c0, c1) = ((j + 1) % 3, (j + 2) % 3);
+        a[r0][c0] * a[r1][c1] - a[r0][c1] * a[r1][c0]
+    };
+    let det: f64 = (0..3).map(|j| a[0][j] * cofactor(0, j)).sum();
+    // Largest absolute row sum, det scales with its cube
+    let norm = a
+        .iter()
+        .map(|row| row.iter().map(|value| value.abs()).sum::<f64>())
+        .fold(0.0, f64::max);
+    if !det.is_finite() || det.abs() <= f64::EPSILON * norm.powi(3) {
+        return None;
+    }
+    // The inverse is the transposed cofactor matrix over the determinant
+    Some(std::array::from_fn(|i| {
+        std::array::from_fn(|j| cofactor(j, i) / det)
+    }))
+}
+
+/***************************
+           Tests
+***************************/
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::quality::QualityFlags;
+    use float_cmp::approx_eq;
+
+    // Constant 10 m/s^2 along x sampled at 10 Hz, with deterministic noise on the positions
+    fn noisy_records(noise: f64) -> (Vec<TelemetryRecord>, Vec<f64>) {
+        let mut truth: Vec<f64> = Vec::new();
+        let records = (0..200u64)
+            .map(|i| {
+                let t = i as f64 * 0.1;
+                truth.push(5.0 * t * t);
+                // Cheap pseudo random numbers in [-1, 1]
+                let wobble = ((i * 7_919) % 200) as f64 / 100.0 - 1.0;
+                TelemetryRecord {
+                    timestamp_ns: i * 100_000_000,
+                    pos_x: 5.0 * t * t + noise * wobble,
+                    vel_x: 10.0 * t,
+                    ..TelemetryRecord::default()
+                }
+            })
+            .collect();
+        (records, truth)
+    }
+
+    fn rms_error(estimates: &[Estimate], truth: &[f64]) -> f64 {
+        let sum: f64 = estimates
+            .iter()
+            .zip(truth)
+            .map(|(estimate, truth)| (estimate.pos()[0] - truth).powi(2))
+            .sum();
+        (sum / truth.len() as f64).sqrt()
+    }
+
+    #[test]
+    fn filter_and_smoother_reduce_noise() {
+        let (records, truth) = noisy_records(5.0);
+        let measured: f64 = records
+            .iter()
+            .zip(&truth)
+            .map(|(record, truth)| (record.pos_x - truth).powi(2))
+            .sum::<f64>();
+        let measured = (measured / truth.len() as f64).sqrt();
+
+        let filtered = filter(&records, &KalmanConfig::default());
+        let (smoothed, skipped) = smooth(&records, &KalmanConfig::default());
+
+        assert_eq!(records.len(), smoothed.len());
+        assert_eq!(0, skipped);
+        assert!(rms_error(&filtered, &truth) < measured);
+        assert!(rms_error(&smoothed, &truth) < rms_error(&filtered, &truth));
+        // Smoothing uses more data so it is more certain
+        assert!(smoothed[100].pos_sigma() < filtered[100].pos_sigma());
+        assert!(approx_eq!(
+            f64,
+            10.0,
+            smoothed[100].state[0][2],
+            epsilon = 0.5
+        ));
+    }
+
+    #[test]
+    fn flagged_records_are_predicted() {
+        let (mut records, _) = noisy_records(0.0);
+        records[50].flags = QualityFlags::IMPLAUSIBLE_JUMP;
+        records[50].pos_x += 1_000.0;
+
+        let filtered = filter(&records, &KalmanConfig::default());
+
+        assert!(approx_eq!(f64, 125.0, filtered[50].pos()[0], epsilon = 0.5));
+        assert!(filtered[50].pos_sigma() > filtered[49].pos_sigma());
+        assert_eq!(125.0 + 1_000.0, records[50].pos_x);
+        assert_eq!(
+            filtered[50].timestamp_ns,
+            filtered[50].to_record().timestamp_ns
+        );
+    }
+
+    #[test]
+    fn inverse_of_matrix() {
+        let a = [[4.0, 7.0, 2.0], [3.0, 6.0, 1.0], [2.0, 5.0, 3.0]];
+        let identity = mat_mul(&a, &inverse(&a).unwrap());
+        for (i, row) in identity.iter().enumerate() {
+            for (j, value) in row.iter().enumerate() {
+                let expected = if i == j { 1.0 } else { 0.0 };
+                assert!(approx_eq!(f64, expected, *value, epsilon = 1e-12));
+            }
+        }
+        assert_eq!(None, inverse(&[[1.0; 3]; 3]));
+        // Small but well conditioned, like the covariance over a short step
+        let small = a.map(|row| row.map(|value| value * 1e-8));
+        assert!(inverse(&small).is_some());
+        // Too badly conditioned to invert in f64, even though the determinant is large
+        let ill_conditioned = [[1e8, 0.0, 0.0], [0.0, 1e8, 0.0], [0.0, 0.0, 1e-9]];
+        assert_eq!(None, inverse(&ill_conditioned));
+    }
+}
@@ -0,0 +1,171 @@
+use ratatui::layout::Constraint;
+use serde::Deserialize;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+
+// The layout used without a `--layout` file, the dashboard as it has always looked
+const DEFAULT_LAYOUT: &str = include_str!("../layouts/default.toml");
+
+/// Parts of the dashboard that can be placed in the layout
+#[derive(Deserialize, Debug, PartialEq, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum Widget {
+    /// Empty space
+    Spacer,
+    Rocket,
+    /// Status, time, velocity, altitude and position text
+    Readouts,
+    /// Gauge of how far through the flight playback is
+    Progress,
+    Tabs,
+    /// Whatever the selected tab shows
+    TabContent,
+    /// Altitude and velocity chart with the sensor channels
+    Telemetry,
+    /// Residuals, dead reckoning drift or the orbit, as picked for the charts tab
+    Charts,
+    Map,
+    Table,
+    Events,
+}
+
+/// Height of a row or width of a widget, written as a percentage ("15%"), a number of
+/// terminal cells ("3") or "fill" for the space left over
+#[derive(Deserialize, Debug, PartialEq, Clone, Copy)]
+#[serde(try_from = "String")]
+pub struct Size(Constraint);
+
+#[derive(Deserialize, Debug, PartialEq, Clone, Copy)]
+pub struct Cell {
+    pub widget: Widget,
+    #[serde(default)]
+    pub size: Size,
+}
+
+#[derive(Deserialize, Debug, PartialEq, Clone)]
+pub struct Row {
+    pub size: Size,
+    /// Left to right, an empty row is blank space
+    #[serde(default)]
+    pub widgets: Vec<Cell>,
+}
+
+/// Rows of widgets from the top of the screen down
+#[derive(Deserialize, Debug, PartialEq, Clone)]
+pub struct DashboardLayout {
+    pub rows: Vec<Row>,
+}
+
+#[derive(Debug)]
+pub struct LayoutError(String);
+
+impl fmt::Display for LayoutError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid layout: {}", self.0)
+    }
+}
+
+impl Error for LayoutError {}
+
+impl Size {
+    pub fn constraint(self) -> Constraint {
+        self.0
+    }
+}
+
+impl Default for Size {
+    fn default() -> Self {
+        Size(Constraint::Fill(1))
+    }
+}
+
+impl TryFrom<String> for Size {
+    type Error = LayoutError;
+
+    fn try_from(text: String) -> Result<Self, Self::Error> {
+        let invalid = || LayoutError(format!("{text} is not a percentage, cell count or fill"));
+        let text = text.trim();
+        if text == "fill" {
+            return Ok(Size::default());
+        }
+        if let Some(percent) = text.strip_suffix('%') {
+            return match percent.trim().parse() {
+                Ok(percent) if percent <= 100 => Ok(Size(Constraint::Percentage(percent))),
+                _ => Err(invalid()),
+            };
+        }
+        text.parse()
+            .map(|cells| Size(Constraint::Length(cells)))
+            .map_err(|_| invalid())
+    }
+}
+
+impl DashboardLayout {
+    pub fn from_file(file_path: &str) -> Result<Self, Box<dyn Error>> {
+        Self::parse(&fs::read_to_string(file_path)?)
+    }
+
+    pub fn parse(text: &str) -> Result<Self, Box<dyn Error>> {
+        let layout: DashboardLayout = toml::from_str(text)?;
+        if layout.rows.is_empty() {
+            return Err(LayoutError("no rows".to_string()).into());
+        }
+        Ok(layout)
+    }
+}
+
+impl Default for DashboardLayout {
+    fn default() -> Self {
+        Self::parse(DEFAULT_LAYOUT).expect("the default layout is valid")
+    }
+}
+
+/***************************
+           Tests
+***************************/
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[test]
+    fn default_layout() {
+        let layout = DashboardLayout::default();
+
+        assert_eq!(5, layout.rows.len());
+        assert_eq!(Constraint::Percentage(15), layout.rows[1].size.constraint());
+        assert_eq!(
+            vec![Widget::Rocket, Widget::Readouts],
+            layout.rows[1]
+                .widgets
+                .iter()
+                .map(|cell| cell.widget)
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            Constraint::Fill(1),
+            layout.rows[4].widgets[0].size.constraint()
+        );
+    }
+
+    #[rstest]
+    #[case("25%", Some(Constraint::Percentage(25)))]
+    #[case("3", Some(Constraint::Length(3)))]
+    #[case(" fill ", Some(Constraint::Fill(1)))]
+    #[case("150%", None)]
+    #[case("-1", None)]
+    #[case("half", None)]
+    fn size_test(#[case] text: &str, #[case] expected: Option<Constraint>) {
+        let actual = Size::try_from(text.to_string()).ok().map(Size::constraint);
+        assert_eq!(expected, actual);
+    }
+
+    #[rstest]
+    #[case("rows = []")]
+    #[case("[[rows]]\nwidgets = [{ widget = \"map\" }]")]
+    #[case("[[rows]]\nsize = \"fill\"\nwidgets = [{ widget = \"clock\" }]")]
+    fn bad_layout(#[case] text: &str) {
+        assert!(DashboardLayout::parse(text).is_err());
+    }
+}
@@ -0,0 +1,6 @@
+//! Coordinate conversions shared with the benchmarks
+
+pub mod batch;
+pub mod coord;
+pub mod geodetic;
+pub mod grid;
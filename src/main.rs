@@ -1,22 +1,408 @@
-mod coord;
+mod ballistic;
+mod ccsds;
+mod chunking;
 mod csv_reader;
+mod dead_reckoning;
+mod eci;
+mod events;
+mod geoid;
+mod gravity;
+mod input;
+mod kalman;
+mod layout;
+mod merge;
+mod orbit;
+mod parquet_io;
+mod quality;
+mod quat;
+mod resample;
+mod residuals;
 mod tui;
 
+use blue_telemetry_tui::coord::{Datum, Ellipsoid};
+use clap::builder::RangedU64ValueParser;
+use clap::{Parser, ValueEnum};
 use std::error::Error;
 
-use crate::csv_reader::{read_csv_and_chunk, TelemetryRecord};
-use crate::tui::App;
+use crate::ballistic::predict_coast;
+use crate::ccsds::{read_ccsds, DecodeReport, PacketDefinitions};
+use crate::chunking::{chunk_records, ChunkStrategy};
+use crate::csv_reader::{read_csv, TelemetryRecord};
+use crate::dead_reckoning::{dead_reckon, imu_samples};
+use crate::eci::{EciConfig, EciModel};
+use crate::events::{detect_events, pad_altitude, EventKind};
+use crate::geoid::Geoid;
+use crate::kalman::{filter, smooth, KalmanConfig};
+use crate::layout::DashboardLayout;
+use crate::merge::{merge_sensors, read_sensor_csv, JoinMethod, SensorSeries};
+use crate::parquet_io::{read_parquet, write_parquet};
+use crate::quality::{detect_gaps, validate, ValidationConfig, ValidationPolicy};
+use crate::resample::resample;
+use crate::residuals::compute_residuals;
+use crate::tui::{AltitudeRef, App, GridFormat};
 
+/// Terminal UI for Blue Origin New Shepard flight telemetry
+#[derive(Parser, Debug)]
+#[command(version, about)]
+struct Args {
+    /// Telemetry file to play back, csv or parquet
+    #[arg(default_value = "data/truth_fast.csv")]
+    file: String,
+
+    /// Packet definition csv, reads `file` as binary CCSDS space packets when given
+    #[arg(long)]
+    packet_def: Option<String>,
+
+    /// Write the records and derived channels to this parquet file instead of starting the UI
+    #[arg(long)]
+    export: Option<String>,
+
+    /// Earth orientation model for the inertial position and velocity in the export
+    #[arg(long, value_enum, default_value_t = EciModel::Full)]
+    eci_model: EciModel,
+
+    /// UT1 - UTC in seconds for the inertial conversion
+    #[arg(long, default_value_t = 0.0, allow_hyphen_values = true)]
+    dut1: f64,
+
+    /// Reference ellipsoid for the geodetic latitude, longitude and altitude
+    #[arg(long, value_enum, default_value_t = Datum::Wgs84)]
+    ellipsoid: Datum,
+
+    /// Semi-major axis in meters of a user defined ellipsoid, used instead of `--ellipsoid`
+    #[arg(long, requires = "inverse_flattening")]
+    semi_major_axis: Option<f64>,
+
+    /// Inverse flattening of the user defined ellipsoid, 0 for a sphere
+    #[arg(long, requires = "semi_major_axis")]
+    inverse_flattening: Option<f64>,
+
+    /// Geoid undulation grid in the EGM96 `WW15MGH.GRD` format, to show altitude above mean sea level
+    /// with the WGS84 ellipsoid
+    #[arg(long)]
+    geoid: Option<String>,
+
+    /// What the displayed altitude is measured from, `agl` is above the launch pad
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = AltitudeRef::Ellipsoid,
+        requires_if("msl", "geoid")
+    )]
+    altitude_ref: AltitudeRef,
+
+    /// How the current position is shown, <G> cycles through them during playback
+    #[arg(long, value_enum, default_value_t = GridFormat::LatLon)]
+    grid: GridFormat,
+
+    /// TOML file of the dashboard rows and widgets, see `layouts/default.toml`
+    #[arg(long)]
+    layout: Option<String>,
+
+    /// Resample the telemetry to this uniform rate in Hz before playback or export
+    #[arg(long, value_parser = parse_rate_hz)]
+    resample_hz: Option<f64>,
+
+    /// Longest gap in seconds that resampling will interpolate across
+    #[arg(long, default_value_t = 1.0)]
+    max_gap: f64,
+
+    /// Report a data gap when samples are further apart than this multiple of the nominal interval
+    #[arg(long, default_value_t = 3.0)]
+    gap_multiple: f64,
+
+    /// What to do with records that fail the plausibility checks
+    #[arg(long, value_enum, default_value_t = ValidationPolicy::Flag)]
+    invalid: ValidationPolicy,
+
+    /// Lowest plausible height above the ellipsoid in meters
+    #[arg(long, default_value_t = -1_000.0, allow_hyphen_values = true)]
+    min_altitude: f64,
+
+    /// Allowed distance in meters between a position and where the reported velocity puts it
+    #[arg(long, default_value_t = 100.0)]
+    jump_tolerance: f64,
+
+    /// Sensor csv to merge onto the truth timeline, can be given more than once
+    #[arg(long = "sensor")]
+    sensors: Vec<String>,
+
+    /// Navigation solution csv, in the same layout as the truth file, to compare against truth
+    #[arg(long)]
+    residuals: Option<String>,
+
+    /// Run a Kalman filter or smoother over the records and plot it alongside the raw altitude
+    #[arg(long, value_enum)]
+    kalman: Option<KalmanMode>,
+
+    /// Spectral density of the jerk driving the filter's constant acceleration model, in m^2/s^5
+    #[arg(long, default_value_t = 1.0)]
+    process_noise: f64,
+
+    /// Standard deviation of the position measurements in meters
+    #[arg(long, default_value_t = 5.0)]
+    pos_noise: f64,
+
+    /// Standard deviation of the velocity measurements in m/s
+    #[arg(long, default_value_t = 0.5)]
+    vel_noise: f64,
+
+    /// IMU csv of body frame delta-velocity x, y, z and delta-angle x, y, z to dead-reckon from truth
+    #[arg(long)]
+    imu: Option<String>,
+
+    /// Seconds after the first record to take the initial truth state for dead reckoning
+    #[arg(long, default_value_t = 0.0)]
+    imu_start: f64,
+
+    /// Mass over drag coefficient times area in kg/m^2, adds drag to the apogee and impact predictions
+    #[arg(long)]
+    ballistic_coefficient: Option<f64>,
+
+    /// How sensor samples are matched to the truth timestamps
+    #[arg(long, value_enum, default_value_t = JoinMode::Interpolated)]
+    join: JoinMode,
+
+    /// Furthest apart in seconds that sensor samples can be to be joined
+    #[arg(long, default_value_t = 0.05)]
+    join_tolerance: f64,
+
+    /// How records are grouped into the chunks the UI steps through
+    #[arg(long, value_enum, default_value_t = ChunkMode::Aligned)]
+    chunking: ChunkMode,
+
+    /// Length of a chunk in seconds for the `aligned` and `drift` chunking
+    #[arg(long, default_value_t = 1.0, value_parser = parse_chunk_seconds)]
+    chunk_seconds: f64,
+
+    /// Number of records in a chunk for the `count` chunking
+    #[arg(long, default_value_t = 100, value_parser = RangedU64ValueParser::<usize>::new().range(1..))]
+    chunk_records: usize,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy)]
+enum JoinMode {
+    /// Closest sample within the tolerance
+    Nearest,
+    /// Linear interpolation between samples at most the tolerance apart
+    Interpolated,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy)]
+enum KalmanMode {
+    /// Forward filter, each estimate only uses the records up to it
+    Filter,
+    /// Forward filter followed by a Rauch-Tung-Striebel smoother
+    Smooth,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy)]
+enum ChunkMode {
+    /// Fixed bins aligned to absolute time, gaps give empty bins
+    Aligned,
+    /// Chunks start at the first record more than the chunk length after the previous start
+    Drift,
+    /// Fixed number of records per chunk
+    Count,
+}
+
+// A rate the resampling interval can be rounded to a whole, non-zero number of nanoseconds from
+fn parse_rate_hz(text: &str) -> Result<f64, String> {
+    let rate_hz: f64 = text.parse().map_err(|error| format!("{error}"))?;
+    if rate_hz.is_finite() && rate_hz > 0.0 && rate_hz <= 1e9 {
+        Ok(rate_hz)
+    } else {
+        Err("expected a rate above 0 and at most 1e9 Hz".to_string())
+    }
+}
+
+// A chunk length that is at least a nanosecond once rounded
+fn parse_chunk_seconds(text: &str) -> Result<f64, String> {
+    let seconds: f64 = text.parse().map_err(|error| format!("{error}"))?;
+    if seconds.is_finite() && seconds >= 1e-9 {
+        Ok(seconds)
+    } else {
+        Err("expected a length of at least 1e-9 seconds".to_string())
+    }
+}
+
+fn load_records(
+    args: &Args,
+) -> Result<(Vec<TelemetryRecord>, Option<DecodeReport>), Box<dyn Error>> {
+    if let Some(definition_path) = &args.packet_def {
+        let definitions = PacketDefinitions::from_file(definition_path)?;
+        let (records, report) = read_ccsds(&args.file, &definitions)?;
+        return Ok((records, Some(report)));
+    }
+
+    if args.file.ends_with(".parquet") {
+        Ok((read_parquet(&args.file)?, None))
+    } else {
+        Ok((read_csv(&args.file)?, None))
+    }
+}
+
+#[allow(clippy::cast_possible_truncation)]
+#[allow(clippy::cast_sign_loss)]
 fn main() -> Result<(), Box<dyn Error>> {
     // io::Result<()> { // -> Result<(), Box<dyn std::error::Error>>
-    let file_path = "data/truth_fast.csv";
-    let time_chunk_duration: u64 = 1_000_000_000;
-    let chunks: Vec<Vec<TelemetryRecord>> = read_csv_and_chunk(file_path, time_chunk_duration)?;
+    let args = Args::parse();
+    let time_chunk_duration = (args.chunk_seconds * 1e9).round() as u64;
+    let strategy = match args.chunking {
+        ChunkMode::Aligned => ChunkStrategy::AlignedBins {
+            duration_ns: time_chunk_duration,
+        },
+        ChunkMode::Drift => ChunkStrategy::Drift {
+            duration_ns: time_chunk_duration,
+        },
+        ChunkMode::Count => ChunkStrategy::Count {
+            size: args.chunk_records,
+        },
+    };
+
+    let ellipsoid = match (args.semi_major_axis, args.inverse_flattening) {
+        (Some(a), Some(inverse_flattening)) => Ellipsoid::new(a, inverse_flattening)?,
+        _ => args.ellipsoid.ellipsoid(),
+    };
+    // EGM96 heights are above WGS84, another ellipsoid would put sea level in the wrong place
+    if args.geoid.is_some() && ellipsoid != Ellipsoid::WGS84 {
+        return Err("--geoid needs the WGS84 ellipsoid".into());
+    }
+
+    let (records, decode_report) = load_records(&args)?;
+    let quality_events = detect_gaps(&records, args.gap_multiple);
+    let validation_config = ValidationConfig {
+        policy: args.invalid,
+        min_altitude: args.min_altitude,
+        jump_tolerance: args.jump_tolerance,
+        ellipsoid,
+    };
+    let (mut records, validation_summary) = validate(records, &validation_config);
+
+    if let Some(rate_hz) = args.resample_hz {
+        let interval_ns = (1e9 / rate_hz).round() as u64;
+        let max_gap_ns = (args.max_gap * 1e9).round() as u64;
+        records = resample(&records, interval_ns, max_gap_ns)?;
+    }
+
+    let sensors: Vec<SensorSeries> = args
+        .sensors
+        .iter()
+        .map(|file_path| read_sensor_csv(file_path))
+        .collect::<Result<_, _>>()?;
+    let join_tolerance_ns = (args.join_tolerance * 1e9).round() as u64;
+    let join_method = match args.join {
+        JoinMode::Nearest => JoinMethod::Nearest {
+            tolerance_ns: join_tolerance_ns,
+        },
+        JoinMode::Interpolated => JoinMethod::Interpolated {
+            max_gap_ns: join_tolerance_ns,
+        },
+    };
+    let sensor_channels = merge_sensors(&mut records, &sensors, join_method);
+
+    if let Some(export_path) = &args.export {
+        let events = detect_events(&records, &ellipsoid);
+        let eci = EciConfig {
+            model: args.eci_model,
+            dut1: args.dut1,
+        };
+        write_parquet(
+            export_path,
+            &records,
+            &events,
+            &sensor_channels,
+            &eci,
+            &ellipsoid,
+        )?;
+        return Ok(());
+    }
+
+    let events = detect_events(&records, &ellipsoid);
+    let residuals = match &args.residuals {
+        Some(file_path) => compute_residuals(
+            &records,
+            read_csv(file_path)?,
+            &events,
+            join_tolerance_ns,
+            &ellipsoid,
+        ),
+        None => Vec::new(),
+    };
+
+    let start_ns = records.first().map_or(0, |record| record.timestamp_ns)
+        + (args.imu_start * 1e9).round() as u64;
+    let dead_reckoning = match (
+        &args.imu,
+        records.iter().find(|r| r.timestamp_ns >= start_ns),
+    ) {
+        (Some(file_path), Some(initial)) => {
+            let samples = imu_samples(&read_sensor_csv(file_path)?)?;
+            let propagated = dead_reckon(initial, &samples);
+            compute_residuals(&records, propagated, &events, join_tolerance_ns, &ellipsoid)
+        }
+        _ => Vec::new(),
+    };
+
+    let kalman_config = KalmanConfig {
+        process_noise: args.process_noise,
+        pos_noise: args.pos_noise,
+        vel_noise: args.vel_noise,
+    };
+    let (filtered, unsmoothed) = match args.kalman {
+        Some(KalmanMode::Filter) => (filter(&records, &kalman_config), 0),
+        Some(KalmanMode::Smooth) => smooth(&records, &kalman_config),
+        None => (Vec::new(), 0),
+    };
+
+    // Predictions come down to the altitude of the pad
+    let pad_alt = pad_altitude(&records, &events, &ellipsoid);
+    let predictions = predict_coast(
+        &records,
+        &events,
+        time_chunk_duration,
+        pad_alt.unwrap_or(0.0),
+        args.ballistic_coefficient,
+        &ellipsoid,
+    );
+    let actual_apogee = events
+        .iter()
+        .find(|event| event.kind == EventKind::Apogee)
+        .and_then(|event| {
+            let geo = records[event.index].ecef().to_geo_on(&ellipsoid).ok()?;
+            Some((event.timestamp_ns, geo.alt))
+        });
+
+    let chunks = chunk_records(records, strategy)?;
+    let mut app = App::new(chunks)
+        .with_ellipsoid(ellipsoid)
+        .with_quality_events(quality_events)
+        .with_flight_events(events)
+        .with_validation_summary(validation_summary)
+        .with_sensor_channels(sensor_channels)
+        .with_residuals(residuals)
+        .with_dead_reckoning(dead_reckoning)
+        .with_predictions(predictions, actual_apogee)
+        .with_filtered(filtered, unsmoothed)
+        .with_altitude_ref(args.altitude_ref)
+        .with_grid_format(args.grid);
+    if let Some(alt) = pad_alt {
+        app = app.with_pad_altitude(alt);
+    }
+    if let Some(report) = decode_report {
+        app = app.with_decode_report(report);
+    }
+    if let Some(file_path) = &args.geoid {
+        app = app.with_geoid(Geoid::from_file(file_path)?);
+    }
+    if let Some(file_path) = &args.layout {
+        app = app.with_layout(DashboardLayout::from_file(file_path)?);
+    }
 
     // Boiler plate Ratatui
     let mut terminal = ratatui::init();
     terminal.clear()?;
-    let app_result = App::new(chunks).run(&mut terminal);
+    let app_result = app.run(&mut terminal);
     ratatui::restore();
 
     Ok(app_result?)
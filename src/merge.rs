@@ -0,0 +1,280 @@
+use csv::ReaderBuilder;
+use std::error::Error;
+use std::path::Path;
+
+use crate::csv_reader::TelemetryRecord;
+use crate::input::open_input;
+
+/// The channels of one sensor file, each sampled at the file's own timestamps
+#[derive(Debug, Default, Clone)]
+pub struct SensorSeries {
+    pub name: String,
+    pub channels: Vec<String>,
+    pub timestamps: Vec<u64>,
+    /// One row of channel values per timestamp
+    pub values: Vec<Vec<f64>>,
+}
+
+/// How sensor samples are matched to the timestamps of the truth records
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum JoinMethod {
+    /// Take the closest sample if it is within `tolerance_ns`
+    Nearest { tolerance_ns: u64 },
+    /// Interpolate linearly between the samples either side if they are at most `max_gap_ns` apart
+    Interpolated { max_gap_ns: u64 },
+}
+
+/// Read a sensor csv whose first column is the TAI timestamp in nanoseconds.
+/// Every other column becomes a channel, empty or unparsable cells are stored as NaN.
+/// Rows are sorted by timestamp as the joins search the samples in time order.
+#[allow(clippy::cast_possible_truncation)]
+#[allow(clippy::cast_sign_loss)]
+pub fn read_sensor_csv(file_path: &str) -> Result<SensorSeries, Box<dyn Error>> {
+    let file = open_input(file_path)?;
+    let mut rdr = ReaderBuilder::new().has_headers(true).from_reader(file);
+
+    let name = Path::new(file_path)
+        .file_name()
+        .and_then(|name| name.to_str())
+        .and_then(|name| name.split('.').next())
+        .unwrap_or(file_path)
+        .to_string();
+    let channels: Vec<String> = rdr.headers()?.iter().skip(1).map(str::to_string).collect();
+    let mut rows: Vec<(u64, Vec<f64>)> = Vec::new();
+
+    for result in rdr.records() {
+        let record = result?;
+        // Timestamps can be in scientific notation like the truth file
+        let timestamp_ns = record.get(0).unwrap().parse::<f64>()?.round() as u64;
+        let values: Vec<f64> = (1..=channels.len())
+            .map(|i| {
+                record
+                    .get(i)
+                    .and_then(|cell| cell.trim().parse::<f64>().ok())
+                    .unwrap_or(f64::NAN)
+            })
+            .collect();
+
+        rows.push((timestamp_ns, values));
+    }
+
+    // Stable, so samples sharing a timestamp keep their file order
+    rows.sort_by_key(|(timestamp_ns, _)| *timestamp_ns);
+    let (timestamps, values) = rows.into_iter().unzip();
+    Ok(SensorSeries {
+        name,
+        channels,
+        timestamps,
+        values,
+    })
+}
+
+impl SensorSeries {
+    /// Values of every channel at `timestamp_ns`, `None` where the sensor has no data
+    #[allow(clippy::cast_precision_loss)]
+    pub fn sample(&self, timestamp_ns: u64, method: JoinMethod) -> Vec<Option<f64>> {
+        let missing = vec![None; self.channels.len()];
+        // Index of the first sample after the timestamp
+        let after = self.timestamps.partition_point(|t| *t <= timestamp_ns);
+
+        let row = |index: usize| -> Vec<Option<f64>> {
+            self.values[index]
+                .iter()
+                .map(|value| value.is_finite().then_some(*value))
+                .collect()
+        };
+
+        match method {
+            JoinMethod::Nearest { tolerance_ns } => {
+                let nearest = [after.checked_sub(1), Some(after)]
+                    .into_iter()
+                    .flatten()
+                    .filter(|index| *index < self.timestamps.len())
+                    .min_by_key(|index| self.timestamps[*index].abs_diff(timestamp_ns));
+
+                match nearest {
+                    Some(index)
+                        if self.timestamps[index].abs_diff(timestamp_ns) <= tolerance_ns =>
+                    {
+                        row(index)
+                    }
+                    _ => missing,
+                }
+            }
+            JoinMethod::Interpolated { max_gap_ns } => {
+                let Some(before) = after.checked_sub(1) else {
+                    return missing;
+                };
+                if self.timestamps[before] == timestamp_ns {
+                    return row(before);
+                }
+                if after >= self.timestamps.len()
+                    || self.timestamps[after] - self.timestamps[before] > max_gap_ns
+                {
+                    return missing;
+                }
+
+                let t = (timestamp_ns - self.timestamps[before]) as f64
+                    / (self.timestamps[after] - self.timestamps[before]) as f64;
+                row(before)
+                    .into_iter()
+                    .zip(row(after))
+                    .map(|(a, b)| Some(a? + t * (b? - a?)))
+                    .collect()
+            }
+        }
+    }
+}
+
+/// Attach the channels of every sensor to the records, aligned by timestamp.
+/// Returns the channel names qualified with the sensor name, e.g. `imu.accel_x`, in the order
+/// they are stored in `TelemetryRecord::sensors`. Sensors with the same name are numbered from
+/// the second one on, e.g. `imu_2.accel_x`, so every channel name is unique.
+pub fn merge_sensors(
+    records: &mut [TelemetryRecord],
+    sensors: &[SensorSeries],
+    method: JoinMethod,
+) -> Vec<String> {
+    for record in records.iter_mut() {
+        record.sensors = sensors
+            .iter()
+            .flat_map(|sensor| sensor.sample(record.timestamp_ns, method))
+            .collect();
+    }
+
+    let mut names: Vec<String> = Vec::new();
+    for sensor in sensors {
+        let mut name = sensor.name.clone();
+        let mut number = 1;
+        while names.contains(&name) {
+            number += 1;
+            name = format!("{}_{number}", sensor.name);
+        }
+        names.push(name);
+    }
+
+    sensors
+        .iter()
+        .zip(&names)
+        .flat_map(|(sensor, name)| {
+            sensor
+                .channels
+                .iter()
+                .map(move |channel| format!("{name}.{channel}"))
+        })
+        .collect()
+}
+
+/***************************
+           Tests
+***************************/
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    fn series() -> SensorSeries {
+        SensorSeries {
+            name: "lidar".to_string(),
+            channels: vec!["range".to_string(), "quality".to_string()],
+            timestamps: vec![100, 200, 300, 600],
+            values: vec![
+                vec![10.0, 1.0],
+                vec![20.0, f64::NAN],
+                vec![30.0, 3.0],
+                vec![60.0, 6.0],
+            ],
+        }
+    }
+
+    #[rstest]
+    #[case(100, vec![Some(10.0), Some(1.0)])]
+    #[case(140, vec![Some(10.0), Some(1.0)])]
+    #[case(160, vec![Some(20.0), None])]
+    #[case(450, vec![None, None])]
+    #[case(40, vec![None, None])]
+    #[case(700, vec![None, None])]
+    fn nearest_join(#[case] timestamp_ns: u64, #[case] expected: Vec<Option<f64>>) {
+        let method = JoinMethod::Nearest { tolerance_ns: 50 };
+        assert_eq!(expected, series().sample(timestamp_ns, method));
+    }
+
+    #[rstest]
+    #[case(100, vec![Some(10.0), Some(1.0)])]
+    #[case(250, vec![Some(25.0), None])]
+    #[case(450, vec![None, None])]
+    #[case(50, vec![None, None])]
+    #[case(600, vec![Some(60.0), Some(6.0)])]
+    #[case(700, vec![None, None])]
+    fn interpolated_join(#[case] timestamp_ns: u64, #[case] expected: Vec<Option<f64>>) {
+        let method = JoinMethod::Interpolated { max_gap_ns: 100 };
+        assert_eq!(expected, series().sample(timestamp_ns, method));
+    }
+
+    #[test]
+    fn merge_sensors_onto_records() {
+        let mut records: Vec<TelemetryRecord> = [100, 250]
+            .iter()
+            .map(|timestamp_ns| TelemetryRecord {
+                timestamp_ns: *timestamp_ns,
+                ..TelemetryRecord::default()
+            })
+            .collect();
+
+        let names = merge_sensors(
+            &mut records,
+            &[series()],
+            JoinMethod::Interpolated { max_gap_ns: 100 },
+        );
+
+        assert_eq!(vec!["lidar.range", "lidar.quality"], names);
+        assert_eq!(vec![Some(25.0), None], records[1].sensors);
+    }
+
+    #[test]
+    fn same_sensor_names() {
+        let mut records = vec![TelemetryRecord::default()];
+
+        let names = merge_sensors(
+            &mut records,
+            &[series(), series(), series()],
+            JoinMethod::Nearest { tolerance_ns: 0 },
+        );
+
+        assert_eq!(
+            vec![
+                "lidar.range",
+                "lidar.quality",
+                "lidar_2.range",
+                "lidar_2.quality",
+                "lidar_3.range",
+                "lidar_3.quality"
+            ],
+            names
+        );
+    }
+
+    #[test]
+    fn unsorted_sensor_file() {
+        let file_path = std::env::temp_dir().join("blue_telemetry_tui_unsorted.csv");
+        std::fs::write(&file_path, "time,range\n300,3\n100,1\n200,2\n").unwrap();
+
+        let series = read_sensor_csv(file_path.to_str().unwrap()).unwrap();
+        std::fs::remove_file(&file_path).unwrap();
+
+        assert_eq!(vec![100, 200, 300], series.timestamps);
+        assert_eq!(vec![vec![1.0], vec![2.0], vec![3.0]], series.values);
+        let method = JoinMethod::Interpolated { max_gap_ns: 100 };
+        assert_eq!(vec![Some(2.5)], series.sample(250, method));
+    }
+
+    #[test]
+    fn read_sensor_file() {
+        let series = read_sensor_csv("data/test.csv").unwrap();
+
+        assert_eq!("test", series.name);
+        assert_eq!(10, series.channels.len());
+        assert_eq!("truth_pos_CON_ECEF_ECEF_M_1", series.channels[0]);
+        assert_eq!(6, series.timestamps.len());
+    }
+}
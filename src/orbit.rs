@@ -0,0 +1,163 @@
+use crate::gravity::{EARTH_RATE, GM};
+
+/// Osculating two-body orbit of a state vector
+#[derive(Debug, Default, PartialEq, Clone, Copy)]
+pub struct OrbitalElements {
+    /// Specific mechanical energy in J/kg, negative while bound to the Earth
+    pub energy: f64,
+    /// In meters, negative for escape trajectories
+    pub semi_major_axis: f64,
+    pub eccentricity: f64,
+    /// In degrees
+    pub inclination: f64,
+    /// Distances from the Earth's center in meters
+    pub apogee_radius: f64,
+    pub perigee_radius: f64,
+}
+
+impl OrbitalElements {
+    /// Energy in J/kg still needed to reach a circular orbit at the current radius
+    pub fn energy_to_orbit(&self, radius: f64) -> f64 {
+        -GM / (2.0 * radius) - self.energy
+    }
+}
+
+/// Orbital elements from an ECEF position and velocity. The velocity is made inertial
+/// by adding the Earth's rotation, in the inertial frame aligned with ECEF at that instant.
+pub fn elements(pos: &[f64; 3], vel: &[f64; 3]) -> OrbitalElements {
+    let vel = [
+        vel[0] - EARTH_RATE * pos[1],
+        vel[1] + EARTH_RATE * pos[0],
+        vel[2],
+    ];
+    let r = norm(pos);
+    let v2 = dot(&vel, &vel);
+
+    let energy = 0.5 * v2 - GM / r;
+    let semi_major_axis = -GM / (2.0 * energy);
+
+    let h = cross(pos, &vel);
+    let inclination = (h[2] / norm(&h)).acos().to_degrees();
+
+    let radial = dot(pos, &vel);
+    let e_vec: [f64; 3] = std::array::from_fn(|i| ((v2 - GM / r) * pos[i] - radial * vel[i]) / GM);
+    let eccentricity = norm(&e_vec);
+
+    let apogee_radius = if eccentricity < 1.0 {
+        semi_major_axis * (1.0 + eccentricity)
+    } else {
+        f64::INFINITY
+    };
+    // Periapsis from the angular momentum works for every conic
+    let perigee_radius = dot(&h, &h) / GM / (1.0 + eccentricity);
+
+    OrbitalElements {
+        energy,
+        semi_major_axis,
+        eccentricity,
+        inclination,
+        apogee_radius,
+        perigee_radius,
+    }
+}
+
+fn dot(a: &[f64; 3], b: &[f64; 3]) -> f64 {
+    a.iter().zip(b).map(|(a, b)| a * b).sum()
+}
+
+fn norm(a: &[f64; 3]) -> f64 {
+    dot(a, a).sqrt()
+}
+
+fn cross(a: &[f64; 3], b: &[f64; 3]) -> [f64; 3] {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+/***************************
+           Tests
+***************************/
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use blue_telemetry_tui::coord::Ellipsoid;
+    use float_cmp::approx_eq;
+    use rstest::rstest;
+
+    const A: f64 = Ellipsoid::WGS84.a;
+
+    const RADIUS: f64 = A + 400_000.0;
+
+    // Inertial velocity for a circular orbit at RADIUS, turned into ECEF over the x axis
+    fn ecef_velocity(inertial: [f64; 3]) -> [f64; 3] {
+        [inertial[0], inertial[1] - EARTH_RATE * RADIUS, inertial[2]]
+    }
+
+    #[rstest]
+    #[case([0.0, 1.0, 0.0], 0.0)]
+    #[case([0.0, 0.0, 1.0], 90.0)]
+    #[case([0.0, -1.0, 0.0], 180.0)]
+    fn circular_orbits(#[case] direction: [f64; 3], #[case] inclination: f64) {
+        let speed = (GM / RADIUS).sqrt();
+        let vel = ecef_velocity(direction.map(|c| c * speed));
+
+        let actual = elements(&[RADIUS, 0.0, 0.0], &vel);
+
+        assert!(approx_eq!(
+            f64,
+            RADIUS,
+            actual.semi_major_axis,
+            epsilon = 1e-6
+        ));
+        assert!(approx_eq!(f64, 0.0, actual.eccentricity, epsilon = 1e-12));
+        assert!(approx_eq!(
+            f64,
+            inclination,
+            actual.inclination,
+            epsilon = 1e-9
+        ));
+        assert!(approx_eq!(
+            f64,
+            RADIUS,
+            actual.perigee_radius,
+            epsilon = 1e-6
+        ));
+        assert!(approx_eq!(
+            f64,
+            0.0,
+            actual.energy_to_orbit(RADIUS),
+            epsilon = 1e-6
+        ));
+    }
+
+    #[test]
+    fn suborbital_hop() {
+        // Straight up at 1 km/s, the orbit is a thin ellipse dipping deep below the surface
+        let actual = elements(&[A, 0.0, 0.0], &[1_000.0, 0.0, 0.0]);
+
+        assert!(actual.energy < 0.0);
+        assert!(actual.eccentricity > 0.99);
+        assert!(actual.perigee_radius < A / 100.0);
+        assert!(actual.apogee_radius > A && actual.apogee_radius < A + 60_000.0);
+        assert!(actual.energy_to_orbit(A) > 2.5e7);
+    }
+
+    #[test]
+    fn escape_velocity() {
+        let speed = 1.01 * (2.0 * GM / RADIUS).sqrt();
+        let actual = elements(&[RADIUS, 0.0, 0.0], &ecef_velocity([0.0, speed, 0.0]));
+
+        assert!(actual.energy > 0.0);
+        assert!(actual.semi_major_axis < 0.0);
+        assert_eq!(f64::INFINITY, actual.apogee_radius);
+        assert!(approx_eq!(
+            f64,
+            RADIUS,
+            actual.perigee_radius,
+            epsilon = 1e-3
+        ));
+    }
+}
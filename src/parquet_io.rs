@@ -0,0 +1,550 @@
+use arrow_array::{
+    Array, ArrayRef, Float64Array, Int64Array, RecordBatch, StringArray, UInt64Array, UInt8Array,
+};
+use arrow_schema::{DataType, Field, Schema};
+use blue_telemetry_tui::batch::{to_geo_batch, EcefSlices, GeoSlicesMut};
+use blue_telemetry_tui::coord::{Ellipsoid, GeoCoord};
+use blue_telemetry_tui::grid::Utm;
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use parquet::arrow::ArrowWriter;
+use parquet::basic::Compression;
+use parquet::file::properties::WriterProperties;
+use std::error::Error;
+use std::fmt;
+use std::fs::File;
+use std::sync::Arc;
+
+use crate::csv_reader::TelemetryRecord;
+use crate::eci::{ecef_to_eci, EciConfig, EciState};
+use crate::events::FlightEvent;
+use crate::quat;
+
+// Rows per record batch when exporting
+const BATCH_SIZE: usize = 8_192;
+
+/// Accepted column names for each record field, our export names first then the NASA csv headers
+const TIMESTAMP_COLUMNS: [&str; 2] = ["timestamp_ns", "TIME_NANOSECONDS_TAI"];
+const POSITION_COLUMNS: [[&str; 2]; 3] = [
+    ["pos_x", "truth_pos_CON_ECEF_ECEF_M_1"],
+    ["pos_y", "truth_pos_CON_ECEF_ECEF_M_2"],
+    ["pos_z", "truth_pos_CON_ECEF_ECEF_M_3"],
+];
+const VELOCITY_COLUMNS: [[&str; 2]; 3] = [
+    ["vel_x", "truth_vel_CON_ECEF_ECEF_MpS_1"],
+    ["vel_y", "truth_vel_CON_ECEF_ECEF_MpS_2"],
+    ["vel_z", "truth_vel_CON_ECEF_ECEF_MpS_3"],
+];
+const QUAT_COLUMNS: [[&str; 2]; 4] = [
+    ["quat_1", "truth_quat_CON2ECEF_1"],
+    ["quat_2", "truth_quat_CON2ECEF_2"],
+    ["quat_3", "truth_quat_CON2ECEF_3"],
+    ["quat_4", "truth_quat_CON2ECEF_4"],
+];
+
+#[derive(Debug)]
+pub enum ParquetColumnError {
+    Missing(String),
+    UnsupportedType(String, DataType),
+    /// Column name and the first row without a value
+    Null(String, usize),
+    /// Column name and the first row with a negative or NaN timestamp
+    InvalidTimestamp(String, usize),
+}
+
+impl fmt::Display for ParquetColumnError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParquetColumnError::Missing(name) => write!(f, "missing column `{name}`"),
+            ParquetColumnError::UnsupportedType(name, data_type) => {
+                write!(f, "column `{name}` has unsupported type {data_type}")
+            }
+            ParquetColumnError::Null(name, row) => {
+                write!(f, "column `{name}` has no value in row {row}")
+            }
+            ParquetColumnError::InvalidTimestamp(name, row) => {
+                write!(f, "column `{name}` has an invalid timestamp in row {row}")
+            }
+        }
+    }
+}
+
+impl Error for ParquetColumnError {}
+
+pub fn read_parquet(file_path: &str) -> Result<Vec<TelemetryRecord>, Box<dyn Error>> {
+    let file = File::open(file_path)?;
+    let reader = ParquetRecordBatchReaderBuilder::try_new(file)?.build()?;
+
+    let mut records: Vec<TelemetryRecord> = Vec::new();
+    for batch in reader {
+        let batch = batch?;
+        let timestamps = column_values(&batch, &TIMESTAMP_COLUMNS)?;
+        let [pos_x, pos_y, pos_z] = POSITION_COLUMNS.map(|names| column_values(&batch, &names));
+        let [vel_x, vel_y, vel_z] = VELOCITY_COLUMNS.map(|names| column_values(&batch, &names));
+        let (pos_x, pos_y, pos_z) = (pos_x?, pos_y?, pos_z?);
+        let (vel_x, vel_y, vel_z) = (vel_x?, vel_y?, vel_z?);
+        // Attitude is optional, but all four components have to be there
+        let quat: Vec<_> = QUAT_COLUMNS
+            .iter()
+            .map(|names| column_values(&batch, names))
+            .collect();
+        let quat = if quat
+            .iter()
+            .all(|column| matches!(column, Err(ParquetColumnError::Missing(_))))
+        {
+            None
+        } else {
+            Some(quat.into_iter().collect::<Result<Vec<Column>, _>>()?)
+        };
+
+        for row in 0..batch.num_rows() {
+            records.push(TelemetryRecord {
+                timestamp_ns: timestamps.u64(row)?,
+                pos_x: pos_x.f64(row),
+                pos_y: pos_y.f64(row),
+                pos_z: pos_z.f64(row),
+                vel_x: vel_x.f64(row),
+                vel_y: vel_y.f64(row),
+                vel_z: vel_z.f64(row),
+                quat: quat
+                    .as_ref()
+                    .map_or(quat::IDENTITY, |q| std::array::from_fn(|i| q[i].f64(row))),
+                ..TelemetryRecord::default()
+            });
+        }
+    }
+
+    Ok(records)
+}
+
+// A numeric column of whichever width the file was written with
+struct Column<'a> {
+    name: &'a str,
+    values: Values<'a>,
+}
+
+enum Values<'a> {
+    Float(&'a Float64Array),
+    Signed(&'a Int64Array),
+    Unsigned(&'a UInt64Array),
+}
+
+impl Column<'_> {
+    /// Nanoseconds, an error for negative values rather than wrapping them around
+    #[allow(clippy::cast_possible_truncation)]
+    #[allow(clippy::cast_sign_loss)]
+    fn u64(&self, row: usize) -> Result<u64, ParquetColumnError> {
+        let invalid = || ParquetColumnError::InvalidTimestamp(self.name.to_string(), row);
+        match self.values {
+            // Timestamps in the NASA files are floats in scientific notation
+            Values::Float(array) => {
+                let value = array.value(row).round();
+                if value >= 0.0 {
+                    Ok(value as u64)
+                } else {
+                    Err(invalid())
+                }
+            }
+            Values::Signed(array) => u64::try_from(array.value(row)).map_err(|_| invalid()),
+            Values::Unsigned(array) => Ok(array.value(row)),
+        }
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    fn f64(&self, row: usize) -> f64 {
+        match self.values {
+            Values::Float(array) => array.value(row),
+            Values::Signed(array) => array.value(row) as f64,
+            Values::Unsigned(array) => array.value(row) as f64,
+        }
+    }
+}
+
+fn column_values<'a>(
+    batch: &'a RecordBatch,
+    names: &[&'a str],
+) -> Result<Column<'a>, ParquetColumnError> {
+    let (name, array) = names
+        .iter()
+        .find_map(|name| batch.column_by_name(name).map(|array| (*name, array)))
+        .ok_or_else(|| ParquetColumnError::Missing(names[0].to_string()))?;
+    if array.null_count() > 0 {
+        let row = (0..array.len())
+            .find(|&row| array.is_null(row))
+            .unwrap_or(0);
+        return Err(ParquetColumnError::Null(name.to_string(), row));
+    }
+    let any = array.as_any();
+
+    let values = if let Some(array) = any.downcast_ref::<Float64Array>() {
+        Values::Float(array)
+    } else if let Some(array) = any.downcast_ref::<Int64Array>() {
+        Values::Signed(array)
+    } else if let Some(array) = any.downcast_ref::<UInt64Array>() {
+        Values::Unsigned(array)
+    } else {
+        return Err(ParquetColumnError::UnsupportedType(
+            name.to_string(),
+            array.data_type().clone(),
+        ));
+    };
+    Ok(Column { name, values })
+}
+
+fn export_schema(sensor_channels: &[String]) -> Schema {
+    let float = |name: &str| Field::new(name, DataType::Float64, false);
+    let mut fields = vec![
+        Field::new("timestamp_ns", DataType::UInt64, false),
+        float("pos_x"),
+        float("pos_y"),
+        float("pos_z"),
+        float("vel_x"),
+        float("vel_y"),
+        float("vel_z"),
+        float("quat_1"),
+        float("quat_2"),
+        float("quat_3"),
+        float("quat_4"),
+        float("lat"),
+        float("lon"),
+        float("alt"),
+        float("vel_east"),
+        float("vel_north"),
+        float("vel_up"),
+        float("speed"),
+        float("eci_pos_x"),
+        float("eci_pos_y"),
+        float("eci_pos_z"),
+        float("eci_vel_x"),
+        float("eci_vel_y"),
+        float("eci_vel_z"),
+        float("inertial_speed"),
+        float("flight_path_angle"),
+        Field::new("utm_zone", DataType::Utf8, true),
+        float("utm_easting"),
+        float("utm_northing"),
+        Field::new("mgrs", DataType::Utf8, true),
+        Field::new("quality_flags", DataType::UInt8, false),
+        Field::new("event", DataType::Utf8, true),
+    ];
+    fields.extend(
+        sensor_channels
+            .iter()
+            .map(|name| Field::new(name, DataType::Float64, true)),
+    );
+    Schema::new(fields)
+}
+
+/// Write the records along with their geodetic position, local velocity, inertial state,
+/// flight events and any merged sensor channels
+pub fn write_parquet(
+    file_path: &str,
+    records: &[TelemetryRecord],
+    events: &[FlightEvent],
+    sensor_channels: &[String],
+    eci: &EciConfig,
+    ellipsoid: &Ellipsoid,
+) -> Result<(), Box<dyn Error>> {
+    let schema = Arc::new(export_schema(sensor_channels));
+    let props = WriterProperties::builder()
+        .set_compression(Compression::SNAPPY)
+        .build();
+    let file = File::create(file_path)?;
+    let mut writer = ArrowWriter::try_new(file, schema.clone(), Some(props))?;
+
+    for (batch_index, batch) in records.chunks(BATCH_SIZE).enumerate() {
+        let offset = batch_index * BATCH_SIZE;
+        // Positions without geodetic coordinates are exported as NaN
+        let x: Vec<f64> = batch.iter().map(|r| r.pos_x).collect();
+        let y: Vec<f64> = batch.iter().map(|r| r.pos_y).collect();
+        let z: Vec<f64> = batch.iter().map(|r| r.pos_z).collect();
+        let (mut lat, mut lon, mut alt) = (
+            vec![0.0; batch.len()],
+            vec![0.0; batch.len()],
+            vec![0.0; batch.len()],
+        );
+        to_geo_batch(
+            EcefSlices {
+                x: &x,
+                y: &y,
+                z: &z,
+            },
+            GeoSlicesMut {
+                lat: &mut lat,
+                lon: &mut lon,
+                alt: &mut alt,
+            },
+            ellipsoid,
+        )?;
+        let enu: Vec<[f64; 3]> = batch
+            .iter()
+            .enumerate()
+            .map(|(i, record)| {
+                let geo = GeoCoord {
+                    lat: lat[i],
+                    lon: lon[i],
+                    alt: alt[i],
+                };
+                geo.ecef_to_enu(record.vel_x, record.vel_y, record.vel_z)
+            })
+            .collect();
+        // Positions outside 80 S to 84 N have no grid reference
+        let utm: Vec<Option<Utm>> = (0..batch.len())
+            .map(|i| {
+                let geo = GeoCoord {
+                    lat: lat[i],
+                    lon: lon[i],
+                    alt: alt[i],
+                };
+                geo.to_utm(ellipsoid)
+            })
+            .collect();
+        let inertial: Vec<EciState> = batch
+            .iter()
+            .map(|r| {
+                let pos = [r.pos_x, r.pos_y, r.pos_z];
+                ecef_to_eci(r.timestamp_ns, &pos, &[r.vel_x, r.vel_y, r.vel_z], eci)
+            })
+            .collect();
+        // Events that land on the same row are joined, e.g. "Max Q;MECO"
+        let event_labels: Vec<Option<String>> = (offset..offset + batch.len())
+            .map(|index| {
+                let labels: Vec<&str> = events
+                    .iter()
+                    .filter(|event| event.index == index)
+                    .map(|event| event.kind.label())
+                    .collect();
+                (!labels.is_empty()).then(|| labels.join(";"))
+            })
+            .collect();
+
+        let float = |values: Vec<f64>| Arc::new(Float64Array::from(values)) as ArrayRef;
+        let mut columns: Vec<ArrayRef> = vec![
+            Arc::new(UInt64Array::from_iter_values(
+                batch.iter().map(|r| r.timestamp_ns),
+            )),
+            float(x),
+            float(y),
+            float(z),
+            float(batch.iter().map(|r| r.vel_x).collect()),
+            float(batch.iter().map(|r| r.vel_y).collect()),
+            float(batch.iter().map(|r| r.vel_z).collect()),
+            float(batch.iter().map(|r| r.quat[0]).collect()),
+            float(batch.iter().map(|r| r.quat[1]).collect()),
+            float(batch.iter().map(|r| r.quat[2]).collect()),
+            float(batch.iter().map(|r| r.quat[3]).collect()),
+            float(lat),
+            float(lon),
+            float(alt),
+            float(enu.iter().map(|v| v[0]).collect()),
+            float(enu.iter().map(|v| v[1]).collect()),
+            float(enu.iter().map(|v| v[2]).collect()),
+            float(batch.iter().map(TelemetryRecord::speed).collect()),
+            float(inertial.iter().map(|s| s.pos[0]).collect()),
+            float(inertial.iter().map(|s| s.pos[1]).collect()),
+            float(inertial.iter().map(|s| s.pos[2]).collect()),
+            float(inertial.iter().map(|s| s.vel[0]).collect()),
+            float(inertial.iter().map(|s| s.vel[1]).collect()),
+            float(inertial.iter().map(|s| s.vel[2]).collect()),
+            float(inertial.iter().map(EciState::speed).collect()),
+            float(inertial.iter().map(EciState::flight_path_angle).collect()),
+            Arc::new(StringArray::from_iter(
+                utm.iter()
+                    .map(|utm| utm.map(|utm| format!("{}{}", utm.zone, utm.band))),
+            )),
+            float(
+                utm.iter()
+                    .map(|utm| utm.map_or(f64::NAN, |utm| utm.easting))
+                    .collect(),
+            ),
+            float(
+                utm.iter()
+                    .map(|utm| utm.map_or(f64::NAN, |utm| utm.northing))
+                    .collect(),
+            ),
+            Arc::new(StringArray::from_iter(
+                utm.iter()
+                    .map(|utm| utm.map(|utm| utm.to_mgrs().to_string())),
+            )),
+            Arc::new(UInt8Array::from_iter_values(
+                batch.iter().map(|r| r.flags.bits()),
+            )),
+            Arc::new(StringArray::from(event_labels)),
+        ];
+        columns.extend((0..sensor_channels.len()).map(|channel| {
+            Arc::new(Float64Array::from(
+                batch
+                    .iter()
+                    .map(|r| r.sensors.get(channel).copied().flatten())
+                    .collect::<Vec<Option<f64>>>(),
+            )) as ArrayRef
+        }));
+        writer.write(&RecordBatch::try_new(schema.clone(), columns)?)?;
+    }
+
+    writer.close()?;
+    Ok(())
+}
+
+/***************************
+           Tests
+***************************/
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::csv_reader::read_csv;
+    use crate::events::EventKind;
+    use rstest::rstest;
+
+    // Write the position and velocity columns with `extra` ones to a file and read it back
+    fn read_back(
+        file_name: &str,
+        extra: Vec<(&str, ArrayRef)>,
+    ) -> Result<Vec<TelemetryRecord>, Box<dyn Error>> {
+        let file_path = std::env::temp_dir().join(file_name);
+        let zeros = || Arc::new(Float64Array::from(vec![0.0, 0.0])) as ArrayRef;
+        let columns = POSITION_COLUMNS
+            .iter()
+            .chain(&VELOCITY_COLUMNS)
+            .map(|names| (names[0], zeros()))
+            .chain(extra);
+        let batch = RecordBatch::try_from_iter(columns).unwrap();
+        let file = File::create(&file_path).unwrap();
+        let mut writer = ArrowWriter::try_new(file, batch.schema(), None).unwrap();
+        writer.write(&batch).unwrap();
+        writer.close().unwrap();
+
+        let records = read_parquet(file_path.to_str().unwrap());
+        std::fs::remove_file(&file_path).unwrap();
+        records
+    }
+
+    #[test]
+    fn parquet_round_trip() {
+        let file_path = std::env::temp_dir().join("blue_telemetry_tui_round_trip.parquet");
+        let file_path = file_path.to_str().unwrap();
+        let expected = read_csv("data/test.csv").unwrap();
+        let event = |kind, index: usize| FlightEvent {
+            kind,
+            index,
+            timestamp_ns: expected[index].timestamp_ns,
+        };
+        let events = vec![
+            event(EventKind::Liftoff, 2),
+            event(EventKind::MaxQ, 5),
+            event(EventKind::Meco, 5),
+        ];
+
+        write_parquet(
+            file_path,
+            &expected,
+            &events,
+            &[],
+            &EciConfig::default(),
+            &Ellipsoid::WGS84,
+        )
+        .unwrap();
+        let actual = read_parquet(file_path).unwrap();
+
+        assert_eq!(expected.len(), actual.len());
+        for (expected, actual) in expected.iter().zip(&actual) {
+            assert_eq!(expected.timestamp_ns, actual.timestamp_ns);
+            assert_eq!(expected.pos_x, actual.pos_x);
+            assert_eq!(expected.vel_z, actual.vel_z);
+            assert_eq!(expected.quat, actual.quat);
+        }
+
+        let file = File::open(file_path).unwrap();
+        let batch = ParquetRecordBatchReaderBuilder::try_new(file)
+            .unwrap()
+            .build()
+            .unwrap()
+            .next()
+            .unwrap()
+            .unwrap();
+        let labels = batch
+            .column_by_name("event")
+            .unwrap()
+            .as_any()
+            .downcast_ref::<StringArray>()
+            .unwrap();
+        assert_eq!(2, labels.len() - labels.null_count());
+        assert_eq!("Liftoff", labels.value(2));
+        assert_eq!("Max Q;MECO", labels.value(5));
+        let mgrs = batch
+            .column_by_name("mgrs")
+            .unwrap()
+            .as_any()
+            .downcast_ref::<StringArray>()
+            .unwrap();
+        assert_eq!(0, mgrs.null_count());
+        assert!(mgrs.value(0).starts_with("13R"));
+
+        std::fs::remove_file(file_path).unwrap();
+    }
+
+    #[test]
+    fn missing_column() {
+        let schema = Arc::new(Schema::new(vec![Field::new(
+            "pos_x",
+            DataType::Float64,
+            false,
+        )]));
+        let batch =
+            RecordBatch::try_new(schema, vec![Arc::new(Float64Array::from(vec![1.0]))]).unwrap();
+        assert!(column_values(&batch, &TIMESTAMP_COLUMNS).is_err());
+        assert!(column_values(&batch, &POSITION_COLUMNS[0]).is_ok());
+    }
+
+    #[test]
+    fn null_value() {
+        let schema = Arc::new(Schema::new(vec![Field::new(
+            "pos_x",
+            DataType::Float64,
+            true,
+        )]));
+        let values = Float64Array::from(vec![Some(1.0), None]);
+        let batch = RecordBatch::try_new(schema, vec![Arc::new(values)]).unwrap();
+
+        let error = column_values(&batch, &POSITION_COLUMNS[0]).err().unwrap();
+        assert_eq!("column `pos_x` has no value in row 1", error.to_string());
+    }
+
+    #[test]
+    fn negative_timestamp() {
+        let timestamps = Arc::new(Int64Array::from(vec![10, -1])) as ArrayRef;
+        let error = read_back(
+            "blue_telemetry_tui_negative.parquet",
+            vec![("TIME_NANOSECONDS_TAI", timestamps)],
+        )
+        .err()
+        .unwrap();
+        assert_eq!(
+            "column `TIME_NANOSECONDS_TAI` has an invalid timestamp in row 1",
+            error.to_string()
+        );
+    }
+
+    #[rstest]
+    #[case(0, Some(quat::IDENTITY))]
+    #[case(2, None)]
+    #[case(4, Some([0.5; 4]))]
+    fn quaternion_columns(#[case] columns: usize, #[case] expected: Option<[f64; 4]>) {
+        let timestamps = Arc::new(UInt64Array::from(vec![10, 20])) as ArrayRef;
+        let quat = QUAT_COLUMNS[..columns].iter().map(|names| {
+            (
+                names[0],
+                Arc::new(Float64Array::from(vec![0.5, 0.5])) as ArrayRef,
+            )
+        });
+        let extra = [("timestamp_ns", timestamps)]
+            .into_iter()
+            .chain(quat)
+            .collect();
+
+        let file_name = format!("blue_telemetry_tui_quat_{columns}.parquet");
+        let actual = read_back(&file_name, extra)
+            .ok()
+            .map(|records| records[1].quat);
+        assert_eq!(expected, actual);
+    }
+}
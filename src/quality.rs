@@ -0,0 +1,353 @@
+use blue_telemetry_tui::coord::Ellipsoid;
+use clap::ValueEnum;
+use std::ops::BitOr;
+
+use crate::csv_reader::TelemetryRecord;
+
+// Largest acceleration in m/s^2 allowed for when checking that positions follow the velocity
+const MAX_ACCELERATION: f64 = 100.0;
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum QualityEventKind {
+    /// No samples arrived for longer than expected
+    Gap,
+}
+
+/// A data quality problem spanning `start_ns` to `end_ns`
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct DataQualityEvent {
+    pub kind: QualityEventKind,
+    pub start_ns: u64,
+    pub end_ns: u64,
+}
+
+/// Bit set of the plausibility checks a record failed
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub struct QualityFlags(u8);
+
+impl QualityFlags {
+    pub const NONE: QualityFlags = QualityFlags(0);
+    /// A position, velocity or attitude value is NaN or infinite
+    pub const NON_FINITE: QualityFlags = QualityFlags(1);
+    /// The position is below the minimum altitude
+    pub const BELOW_SURFACE: QualityFlags = QualityFlags(1 << 1);
+    /// The position moved further than the reported velocity allows
+    pub const IMPLAUSIBLE_JUMP: QualityFlags = QualityFlags(1 << 2);
+
+    pub fn bits(self) -> u8 {
+        self.0
+    }
+
+    pub fn contains(self, other: QualityFlags) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    /// Short names of the checks that failed
+    pub fn labels(self) -> Vec<&'static str> {
+        [
+            (QualityFlags::NON_FINITE, "non-finite"),
+            (QualityFlags::BELOW_SURFACE, "below surface"),
+            (QualityFlags::IMPLAUSIBLE_JUMP, "jump"),
+        ]
+        .into_iter()
+        .filter(|(flag, _)| self.contains(*flag))
+        .map(|(_, label)| label)
+        .collect()
+    }
+}
+
+impl BitOr for QualityFlags {
+    type Output = QualityFlags;
+
+    fn bitor(self, rhs: QualityFlags) -> QualityFlags {
+        QualityFlags(self.0 | rhs.0)
+    }
+}
+
+/// What to do with records that fail a plausibility check
+#[derive(ValueEnum, Debug, Default, PartialEq, Clone, Copy)]
+pub enum ValidationPolicy {
+    /// Remove the record
+    Drop,
+    /// Keep the record and mark it with its quality flags
+    #[default]
+    Flag,
+    /// Keep the record unmarked, only count it
+    Keep,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct ValidationConfig {
+    pub policy: ValidationPolicy,
+    /// Lowest plausible height above the ellipsoid in meters
+    pub min_altitude: f64,
+    pub ellipsoid: Ellipsoid,
+    /// Allowed distance in meters between a position and where the velocity says it should be
+    pub jump_tolerance: f64,
+}
+
+impl Default for ValidationConfig {
+    fn default() -> Self {
+        ValidationConfig {
+            policy: ValidationPolicy::Flag,
+            min_altitude: -1_000.0,
+            ellipsoid: Ellipsoid::WGS84,
+            jump_tolerance: 100.0,
+        }
+    }
+}
+
+/// Counts of records failing each check
+#[derive(Debug, Default, PartialEq, Clone, Copy)]
+pub struct ValidationSummary {
+    pub non_finite: usize,
+    pub below_surface: usize,
+    pub implausible_jump: usize,
+    /// Records failing any check
+    pub failed: usize,
+    pub dropped: usize,
+}
+
+/// Check every record for non-finite values, positions below the surface and
+/// positions that jump further than the velocity allows, then apply the policy.
+#[allow(clippy::cast_precision_loss)]
+pub fn validate(
+    records: Vec<TelemetryRecord>,
+    config: &ValidationConfig,
+) -> (Vec<TelemetryRecord>, ValidationSummary) {
+    let mut summary = ValidationSummary::default();
+    let mut validated: Vec<TelemetryRecord> = Vec::with_capacity(records.len());
+    // Jumps are measured from the last record that passed every check
+    let mut last_valid: Option<TelemetryRecord> = None;
+
+    for mut record in records {
+        let mut flags = QualityFlags::NONE;
+
+        let values = [
+            record.pos_x,
+            record.pos_y,
+            record.pos_z,
+            record.vel_x,
+            record.vel_y,
+            record.vel_z,
+        ];
+        if values
+            .iter()
+            .chain(&record.quat)
+            .any(|value| !value.is_finite())
+        {
+            flags = flags | QualityFlags::NON_FINITE;
+        } else {
+            // Positions near the Earth's center have no altitude and are well below the surface
+            let below_surface = record
+                .ecef()
+                .to_geo_on(&config.ellipsoid)
+                .map_or(true, |geo| geo.alt < config.min_altitude);
+            if below_surface {
+                flags = flags | QualityFlags::BELOW_SURFACE;
+            }
+
+            if let Some(previous) = &last_valid {
+                let dt = record.timestamp_ns.saturating_sub(previous.timestamp_ns) as f64 / 1e9;
+                // Trapezoidal step from the previous position using the mean velocity
+                let predicted = [
+                    previous.pos_x + 0.5 * (previous.vel_x + record.vel_x) * dt,
+                    previous.pos_y + 0.5 * (previous.vel_y + record.vel_y) * dt,
+                    previous.pos_z + 0.5 * (previous.vel_z + record.vel_z) * dt,
+                ];
+                let error = ((record.pos_x - predicted[0]).powi(2)
+                    + (record.pos_y - predicted[1]).powi(2)
+                    + (record.pos_z - predicted[2]).powi(2))
+                .sqrt();
+                let tolerance = config.jump_tolerance + 0.5 * MAX_ACCELERATION * dt * dt;
+                if error > tolerance {
+                    flags = flags | QualityFlags::IMPLAUSIBLE_JUMP;
+                }
+            }
+        }
+
+        if flags.contains(QualityFlags::NON_FINITE) {
+            summary.non_finite += 1;
+        }
+        if flags.contains(QualityFlags::BELOW_SURFACE) {
+            summary.below_surface += 1;
+        }
+        if flags.contains(QualityFlags::IMPLAUSIBLE_JUMP) {
+            summary.implausible_jump += 1;
+        }
+
+        if flags.is_empty() {
+            last_valid = Some(record.clone());
+        } else {
+            summary.failed += 1;
+            match config.policy {
+                ValidationPolicy::Drop => {
+                    summary.dropped += 1;
+                    continue;
+                }
+                ValidationPolicy::Flag => record.flags = flags,
+                ValidationPolicy::Keep => {}
+            }
+        }
+        validated.push(record);
+    }
+
+    (validated, summary)
+}
+
+/// The typical spacing of the records, taken as the median time between samples
+pub fn nominal_interval(records: &[TelemetryRecord]) -> Option<u64> {
+    let mut intervals: Vec<u64> = records
+        .windows(2)
+        .map(|pair| pair[1].timestamp_ns.saturating_sub(pair[0].timestamp_ns))
+        .filter(|interval| *interval > 0)
+        .collect();
+    if intervals.is_empty() {
+        return None;
+    }
+
+    intervals.sort_unstable();
+    Some(intervals[intervals.len() / 2])
+}
+
+/// Find every stretch between samples longer than `multiple` times the nominal interval
+#[allow(clippy::cast_precision_loss)]
+pub fn detect_gaps(records: &[TelemetryRecord], multiple: f64) -> Vec<DataQualityEvent> {
+    let Some(nominal) = nominal_interval(records) else {
+        return Vec::new();
+    };
+    let threshold = nominal as f64 * multiple;
+
+    records
+        .windows(2)
+        .filter(|pair| {
+            (pair[1].timestamp_ns.saturating_sub(pair[0].timestamp_ns)) as f64 > threshold
+        })
+        .map(|pair| DataQualityEvent {
+            kind: QualityEventKind::Gap,
+            start_ns: pair[0].timestamp_ns,
+            end_ns: pair[1].timestamp_ns,
+        })
+        .collect()
+}
+
+/***************************
+           Tests
+***************************/
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::csv_reader::read_csv;
+    use rstest::rstest;
+
+    fn records(timestamps: &[u64]) -> Vec<TelemetryRecord> {
+        timestamps
+            .iter()
+            .map(|timestamp_ns| TelemetryRecord {
+                timestamp_ns: *timestamp_ns,
+                ..TelemetryRecord::default()
+            })
+            .collect()
+    }
+
+    #[rstest]
+    #[case(&[], None)]
+    #[case(&[0, 10, 20, 30, 100, 110], Some(10))]
+    #[case(&[0, 10, 10, 30], Some(20))]
+    fn nominal_interval_test(#[case] timestamps: &[u64], #[case] expected: Option<u64>) {
+        assert_eq!(expected, nominal_interval(&records(timestamps)));
+    }
+
+    #[rstest]
+    #[case(3.0, vec![(30, 100)])]
+    #[case(2.0, vec![(30, 100), (110, 135)])]
+    #[case(10.0, vec![])]
+    fn detect_gaps_test(#[case] multiple: f64, #[case] expected: Vec<(u64, u64)>) {
+        let records = records(&[0, 10, 20, 30, 100, 110, 135, 145]);
+
+        let actual: Vec<(u64, u64)> = detect_gaps(&records, multiple)
+            .iter()
+            .map(|gap| (gap.start_ns, gap.end_ns))
+            .collect();
+
+        assert_eq!(expected, actual);
+    }
+
+    fn moving_record(seconds: u64, pos_x: f64, vel_x: f64) -> TelemetryRecord {
+        TelemetryRecord {
+            timestamp_ns: seconds * 1_000_000_000,
+            pos_x,
+            vel_x,
+            ..TelemetryRecord::default()
+        }
+    }
+
+    fn flagged_records() -> Vec<TelemetryRecord> {
+        vec![
+            moving_record(0, 6_378_137.0, 10.0),
+            moving_record(1, 6_378_147.0, 10.0),
+            moving_record(2, f64::NAN, 10.0),
+            // Ten kilometers underground
+            moving_record(3, 6_368_177.0, 10.0),
+            // Five kilometers higher than the velocity allows
+            moving_record(4, 6_383_177.0, 10.0),
+            moving_record(5, 6_378_187.0, 10.0),
+        ]
+    }
+
+    #[rstest]
+    #[case(ValidationPolicy::Flag, 6, 3)]
+    #[case(ValidationPolicy::Keep, 6, 0)]
+    #[case(ValidationPolicy::Drop, 3, 0)]
+    fn validation_policies(
+        #[case] policy: ValidationPolicy,
+        #[case] expected_len: usize,
+        #[case] expected_flagged: usize,
+    ) {
+        let config = ValidationConfig {
+            policy,
+            ..ValidationConfig::default()
+        };
+
+        let (records, summary) = validate(flagged_records(), &config);
+
+        assert_eq!(expected_len, records.len());
+        assert_eq!(
+            expected_flagged,
+            records.iter().filter(|r| !r.flags.is_empty()).count()
+        );
+        assert_eq!(1, summary.non_finite);
+        // Underground is also a jump from the last good position
+        assert_eq!(1, summary.below_surface);
+        assert_eq!(2, summary.implausible_jump);
+        assert_eq!(3, summary.failed);
+    }
+
+    #[test]
+    fn validation_flags() {
+        let (records, _) = validate(flagged_records(), &ValidationConfig::default());
+
+        let flags: Vec<QualityFlags> = records.iter().map(|r| r.flags).collect();
+        assert_eq!(
+            vec![
+                QualityFlags::NONE,
+                QualityFlags::NONE,
+                QualityFlags::NON_FINITE,
+                QualityFlags::BELOW_SURFACE | QualityFlags::IMPLAUSIBLE_JUMP,
+                QualityFlags::IMPLAUSIBLE_JUMP,
+                QualityFlags::NONE,
+            ],
+            flags
+        );
+    }
+
+    #[test]
+    fn detect_gaps_test_csv() {
+        let records = read_csv("data/test.csv").unwrap();
+        assert_eq!(2, detect_gaps(&records, 3.0).len());
+    }
+}
@@ -0,0 +1,117 @@
+//! Quaternion helpers for the attitude columns of the telemetry.
+//! Quaternions are stored as `[f64; 4]` in the column order of the NASA files.
+
+// Below this angle between quaternions slerp falls back to a linear blend
+const SLERP_LINEAR_THRESHOLD: f64 = 0.999_5;
+
+pub const IDENTITY: [f64; 4] = [0.0, 0.0, 0.0, 1.0];
+
+pub fn dot(a: &[f64; 4], b: &[f64; 4]) -> f64 {
+    a.iter().zip(b).map(|(a, b)| a * b).sum()
+}
+
+pub fn normalize(q: [f64; 4]) -> [f64; 4] {
+    let norm = dot(&q, &q).sqrt();
+    q.map(|c| c / norm)
+}
+
+/// Hamilton product `a * b`, the rotation `b` followed by `a`
+pub fn multiply(a: &[f64; 4], b: &[f64; 4]) -> [f64; 4] {
+    let [ax, ay, az, aw] = *a;
+    let [bx, by, bz, bw] = *b;
+    [
+        aw * bx + ax * bw + ay * bz - az * by,
+        aw * by - ax * bz + ay * bw + az * bx,
+        aw * bz + ax * by - ay * bx + az * bw,
+        aw * bw - ax * bx - ay * by - az * bz,
+    ]
+}
+
+/// Rotate a vector by a unit quaternion
+pub fn rotate(q: &[f64; 4], v: &[f64; 3]) -> [f64; 3] {
+    let conjugate = [-q[0], -q[1], -q[2], q[3]];
+    let rotated = multiply(&multiply(q, &[v[0], v[1], v[2], 0.0]), &conjugate);
+    [rotated[0], rotated[1], rotated[2]]
+}
+
+/// Quaternion of a rotation by `|v|` radians about the axis `v`
+pub fn from_rotation_vector(v: &[f64; 3]) -> [f64; 4] {
+    let angle = v.iter().map(|c| c * c).sum::<f64>().sqrt();
+    if angle < 1e-12 {
+        // Small angle approximation avoids dividing by zero
+        return normalize([0.5 * v[0], 0.5 * v[1], 0.5 * v[2], 1.0]);
+    }
+    let (sin, cos) = (0.5 * angle).sin_cos();
+    [
+        v[0] / angle * sin,
+        v[1] / angle * sin,
+        v[2] / angle * sin,
+        cos,
+    ]
+}
+
+/// Spherical linear interpolation between two unit quaternions, `t` in `[0, 1]`
+pub fn slerp(a: &[f64; 4], b: &[f64; 4], t: f64) -> [f64; 4] {
+    let mut cos_theta = dot(a, b);
+
+    // q and -q are the same rotation, take the short way around
+    let mut b = *b;
+    if cos_theta < 0.0 {
+        b = b.map(|c| -c);
+        cos_theta = -cos_theta;
+    }
+
+    if cos_theta > SLERP_LINEAR_THRESHOLD {
+        return normalize(std::array::from_fn(|i| a[i] + t * (b[i] - a[i])));
+    }
+
+    let theta = cos_theta.acos();
+    let sin_theta = theta.sin();
+    let weight_a = ((1.0 - t) * theta).sin() / sin_theta;
+    let weight_b = (t * theta).sin() / sin_theta;
+
+    std::array::from_fn(|i| weight_a * a[i] + weight_b * b[i])
+}
+
+/***************************
+           Tests
+***************************/
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use float_cmp::approx_eq;
+    use rstest::rstest;
+    use std::f64::consts::FRAC_1_SQRT_2;
+
+    // 90 degree rotation about z with the scalar last
+    const QUARTER_TURN_Z: [f64; 4] = [0.0, 0.0, FRAC_1_SQRT_2, FRAC_1_SQRT_2];
+
+    #[rstest]
+    #[case(IDENTITY, QUARTER_TURN_Z, 0.0, IDENTITY)]
+    #[case(IDENTITY, QUARTER_TURN_Z, 1.0, QUARTER_TURN_Z)]
+    #[case(IDENTITY, QUARTER_TURN_Z, 0.5, [0.0, 0.0, (std::f64::consts::PI / 8.0).sin(), (std::f64::consts::PI / 8.0).cos()])]
+    #[case(IDENTITY, QUARTER_TURN_Z.map(|c| -c), 0.5, [0.0, 0.0, (std::f64::consts::PI / 8.0).sin(), (std::f64::consts::PI / 8.0).cos()])]
+    fn slerp_test(
+        #[case] a: [f64; 4],
+        #[case] b: [f64; 4],
+        #[case] t: f64,
+        #[case] expected: [f64; 4],
+    ) {
+        let actual = slerp(&a, &b, t);
+        for (expected, actual) in expected.iter().zip(actual) {
+            assert!(approx_eq!(f64, *expected, actual, epsilon = 1e-12));
+        }
+    }
+
+    #[rstest]
+    #[case(IDENTITY, [1.0, 2.0, 3.0], [1.0, 2.0, 3.0])]
+    #[case(QUARTER_TURN_Z, [1.0, 0.0, 0.0], [0.0, 1.0, 0.0])]
+    #[case(from_rotation_vector(&[std::f64::consts::PI, 0.0, 0.0]), [0.0, 1.0, 1.0], [0.0, -1.0, -1.0])]
+    #[case(multiply(&QUARTER_TURN_Z, &QUARTER_TURN_Z), [1.0, 0.0, 0.0], [-1.0, 0.0, 0.0])]
+    fn rotate_test(#[case] q: [f64; 4], #[case] v: [f64; 3], #[case] expected: [f64; 3]) {
+        let actual = rotate(&q, &v);
+        for (expected, actual) in expected.iter().zip(actual) {
+            assert!(approx_eq!(f64, *expected, actual, epsilon = 1e-12));
+        }
+    }
+}
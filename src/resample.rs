@@ -0,0 +1,187 @@
+use std::error::Error;
+use std::fmt;
+
+use crate::csv_reader::TelemetryRecord;
+use crate::quat;
+
+#[derive(Debug)]
+pub struct ResampleError(String);
+
+impl fmt::Display for ResampleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "cannot resample: {}", self.0)
+    }
+}
+
+impl Error for ResampleError {}
+
+/// Interpolate the records onto a uniform grid of timestamps that are multiples of `interval_ns`.
+/// Grid points between samples further apart than `max_gap_ns` are left out rather than invented.
+/// The records have to be in timestamp order and the interval at least a nanosecond.
+#[allow(clippy::cast_precision_loss)]
+pub fn resample(
+    records: &[TelemetryRecord],
+    interval_ns: u64,
+    max_gap_ns: u64,
+) -> Result<Vec<TelemetryRecord>, ResampleError> {
+    if interval_ns == 0 {
+        return Err(ResampleError("the interval is zero".to_string()));
+    }
+    if let Some(i) = records
+        .windows(2)
+        .position(|pair| pair[1].timestamp_ns < pair[0].timestamp_ns)
+    {
+        return Err(ResampleError(format!(
+            "record {} is earlier than the one before it",
+            i + 1
+        )));
+    }
+    let (Some(first), Some(last)) = (records.first(), records.last()) else {
+        return Ok(Vec::new());
+    };
+
+    let mut resampled: Vec<TelemetryRecord> = Vec::new();
+    let mut timestamp_ns = first.timestamp_ns.div_ceil(interval_ns) * interval_ns;
+    // Index of the sample at or before the current grid point
+    let mut i = 0;
+
+    while timestamp_ns <= last.timestamp_ns {
+        while i + 1 < records.len() && records[i + 1].timestamp_ns <= timestamp_ns {
+            i += 1;
+        }
+
+        let before = &records[i];
+        if before.timestamp_ns == timestamp_ns {
+            resampled.push(before.clone());
+        } else {
+            let after = &records[i + 1];
+            let span = after.timestamp_ns - before.timestamp_ns;
+
+            if span > max_gap_ns {
+                // Jump to the first grid point after the gap
+                timestamp_ns = after.timestamp_ns.div_ceil(interval_ns) * interval_ns;
+                continue;
+            }
+
+            let t = (timestamp_ns - before.timestamp_ns) as f64 / span as f64;
+            resampled.push(interpolate(before, after, timestamp_ns, t));
+        }
+
+        timestamp_ns += interval_ns;
+    }
+
+    Ok(resampled)
+}
+
+fn interpolate(
+    before: &TelemetryRecord,
+    after: &TelemetryRecord,
+    timestamp_ns: u64,
+    t: f64,
+) -> TelemetryRecord {
+    let lerp = |a: f64, b: f64| a + t * (b - a);
+
+    TelemetryRecord {
+        timestamp_ns,
+        pos_x: lerp(before.pos_x, after.pos_x),
+        pos_y: lerp(before.pos_y, after.pos_y),
+        pos_z: lerp(before.pos_z, after.pos_z),
+        vel_x: lerp(before.vel_x, after.vel_x),
+        vel_y: lerp(before.vel_y, after.vel_y),
+        vel_z: lerp(before.vel_z, after.vel_z),
+        quat: quat::slerp(&before.quat, &after.quat, t),
+        // Interpolating from a suspect sample makes the result suspect too
+        flags: before.flags | after.flags,
+        // Sensor channels are merged onto the resampled timeline afterwards
+        sensors: Vec::new(),
+    }
+}
+
+/***************************
+           Tests
+***************************/
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::csv_reader::read_csv;
+    use float_cmp::approx_eq;
+    use std::f64::consts::FRAC_1_SQRT_2;
+
+    fn record(timestamp_ns: u64, pos_x: f64, quat: [f64; 4]) -> TelemetryRecord {
+        TelemetryRecord {
+            timestamp_ns,
+            pos_x,
+            vel_x: pos_x * 2.0,
+            quat,
+            ..TelemetryRecord::default()
+        }
+    }
+
+    #[test]
+    fn resample_interpolates_between_samples() {
+        let quarter_turn = [0.0, 0.0, FRAC_1_SQRT_2, FRAC_1_SQRT_2];
+        let records = vec![
+            record(5, 0.0, quat::IDENTITY),
+            record(45, 40.0, quarter_turn),
+        ];
+
+        let actual = resample(&records, 10, 100).unwrap();
+
+        let timestamps: Vec<u64> = actual.iter().map(|r| r.timestamp_ns).collect();
+        assert_eq!(vec![10, 20, 30, 40], timestamps);
+        assert!(approx_eq!(f64, 5.0, actual[0].pos_x, ulps = 2));
+        assert!(approx_eq!(f64, 70.0, actual[3].vel_x, ulps = 2));
+        // Halfway through the quarter turn is an eighth turn
+        let half = resample(&records, 25, 100).unwrap()[0].clone();
+        assert_eq!(25, half.timestamp_ns);
+        assert!(approx_eq!(
+            f64,
+            (std::f64::consts::PI / 8.0).sin(),
+            half.quat[2],
+            epsilon = 1e-12
+        ));
+    }
+
+    #[test]
+    fn resample_does_not_fill_gaps() {
+        let records = vec![
+            record(0, 0.0, quat::IDENTITY),
+            record(10, 1.0, quat::IDENTITY),
+            record(100, 10.0, quat::IDENTITY),
+            record(110, 11.0, quat::IDENTITY),
+        ];
+
+        let actual = resample(&records, 5, 20).unwrap();
+
+        let timestamps: Vec<u64> = actual.iter().map(|r| r.timestamp_ns).collect();
+        assert_eq!(vec![0, 5, 10, 100, 105, 110], timestamps);
+    }
+
+    #[test]
+    fn resample_test_csv() {
+        // Pairs of samples 10 ms, 10 ms and 380 ms apart with 30 second gaps between them
+        let records = read_csv("data/test.csv").unwrap();
+
+        let actual = resample(&records, 5_000_000, 100_000_000).unwrap();
+
+        assert_eq!(4, actual.len());
+        for pair in actual.chunks(2) {
+            assert_eq!(5_000_000, pair[1].timestamp_ns - pair[0].timestamp_ns);
+        }
+    }
+
+    #[test]
+    fn resample_rejects_bad_input() {
+        let records = vec![
+            record(10, 1.0, quat::IDENTITY),
+            record(0, 0.0, quat::IDENTITY),
+        ];
+
+        assert!(resample(&records[..1], 0, 20).is_err());
+        let error = resample(&records, 5, 20).unwrap_err();
+        assert_eq!(
+            "cannot resample: record 1 is earlier than the one before it",
+            error.to_string()
+        );
+    }
+}
@@ -0,0 +1,244 @@
+use blue_telemetry_tui::coord::Ellipsoid;
+
+use crate::csv_reader::TelemetryRecord;
+use crate::events::{FlightEvent, FlightPhase};
+
+/// Sensor minus truth at one truth timestamp, in the local east, north, up frame
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Residual {
+    pub timestamp_ns: u64,
+    pub phase: FlightPhase,
+    pub pos_enu: [f64; 3],
+    pub vel_enu: [f64; 3],
+}
+
+impl Residual {
+    pub fn pos_norm(&self) -> f64 {
+        norm(&self.pos_enu)
+    }
+
+    pub fn vel_norm(&self) -> f64 {
+        norm(&self.vel_enu)
+    }
+}
+
+/// RMS and maximum of the residual magnitudes over one flight phase
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct PhaseStats {
+    pub phase: FlightPhase,
+    pub samples: usize,
+    pub pos_rms: f64,
+    pub pos_max: f64,
+    pub vel_rms: f64,
+    pub vel_max: f64,
+}
+
+fn norm(v: &[f64; 3]) -> f64 {
+    v.iter().map(|c| c * c).sum::<f64>().sqrt()
+}
+
+/// Compare a sensor solution against truth at every truth timestamp, in the local frame
+/// on `ellipsoid`. The sensor is put in time order and interpolated between samples at most
+/// `max_gap_ns` apart, truth records without sensor data around them get no residual.
+#[allow(clippy::cast_precision_loss)]
+pub fn compute_residuals(
+    truth: &[TelemetryRecord],
+    mut sensor: Vec<TelemetryRecord>,
+    events: &[FlightEvent],
+    max_gap_ns: u64,
+    ellipsoid: &Ellipsoid,
+) -> Vec<Residual> {
+    let mut residuals: Vec<Residual> = Vec::new();
+    // Stable, so samples sharing a timestamp keep their file order
+    sensor.sort_by_key(|s| s.timestamp_ns);
+
+    for record in truth {
+        let after = sensor.partition_point(|s| s.timestamp_ns < record.timestamp_ns);
+        let estimate = match (after.checked_sub(1), sensor.get(after)) {
+            // Exact match
+            (_, Some(at)) if at.timestamp_ns == record.timestamp_ns => state(at),
+            (Some(before), Some(next)) => {
+                let before = &sensor[before];
+                let span = next.timestamp_ns - before.timestamp_ns;
+                if span > max_gap_ns {
+                    continue;
+                }
+                let t = (record.timestamp_ns - before.timestamp_ns) as f64 / span as f64;
+                let (a, b) = (state(before), state(next));
+                std::array::from_fn(|i| a[i] + t * (b[i] - a[i]))
+            }
+            _ => continue,
+        };
+
+        let truth_state = state(record);
+        let Ok(geo) = record.ecef().to_geo_on(ellipsoid) else {
+            continue;
+        };
+        let pos_enu = geo.ecef_to_enu(
+            estimate[0] - truth_state[0],
+            estimate[1] - truth_state[1],
+            estimate[2] - truth_state[2],
+        );
+        let vel_enu = geo.ecef_to_enu(
+            estimate[3] - truth_state[3],
+            estimate[4] - truth_state[4],
+            estimate[5] - truth_state[5],
+        );
+
+        residuals.push(Residual {
+            timestamp_ns: record.timestamp_ns,
+            phase: FlightPhase::at(events, record.timestamp_ns),
+            pos_enu,
+            vel_enu,
+        });
+    }
+
+    residuals
+}
+
+fn state(record: &TelemetryRecord) -> [f64; 6] {
+    [
+        record.pos_x,
+        record.pos_y,
+        record.pos_z,
+        record.vel_x,
+        record.vel_y,
+        record.vel_z,
+    ]
+}
+
+/// Statistics for every phase that has residuals, in flight order
+#[allow(clippy::cast_precision_loss)]
+pub fn phase_stats(residuals: &[Residual]) -> Vec<PhaseStats> {
+    FlightPhase::ALL
+        .iter()
+        .filter_map(|phase| {
+            let in_phase: Vec<&Residual> = residuals.iter().filter(|r| r.phase == *phase).collect();
+            if in_phase.is_empty() {
+                return None;
+            }
+
+            let samples = in_phase.len();
+            let rms = |values: &mut dyn Iterator<Item = f64>| {
+                (values.map(|v| v * v).sum::<f64>() / samples as f64).sqrt()
+            };
+            let max = |values: &mut dyn Iterator<Item = f64>| values.fold(0.0, f64::max);
+
+            Some(PhaseStats {
+                phase: *phase,
+                samples,
+                pos_rms: rms(&mut in_phase.iter().map(|r| r.pos_norm())),
+                pos_max: max(&mut in_phase.iter().map(|r| r.pos_norm())),
+                vel_rms: rms(&mut in_phase.iter().map(|r| r.vel_norm())),
+                vel_max: max(&mut in_phase.iter().map(|r| r.vel_norm())),
+            })
+        })
+        .collect()
+}
+
+/***************************
+           Tests
+***************************/
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::EventKind;
+    use float_cmp::approx_eq;
+
+    // On the equator at the prime meridian, where ECEF x is up and y is east
+    fn record(timestamp_ns: u64, pos_x: f64, pos_y: f64, vel_x: f64) -> TelemetryRecord {
+        TelemetryRecord {
+            timestamp_ns,
+            pos_x: 6_378_137.0 + pos_x,
+            pos_y,
+            vel_x,
+            ..TelemetryRecord::default()
+        }
+    }
+
+    #[test]
+    fn residuals_in_local_frame() {
+        let truth = vec![record(0, 0.0, 0.0, 0.0), record(10, 0.0, 0.0, 0.0)];
+        let sensor = vec![record(0, 3.0, 4.0, 1.0), record(20, 3.0, 8.0, 1.0)];
+
+        let residuals = compute_residuals(&truth, sensor, &[], 100, &Ellipsoid::WGS84);
+
+        assert_eq!(2, residuals.len());
+        let expected_pos = [[4.0, 0.0, 3.0], [6.0, 0.0, 3.0]];
+        for (residual, expected) in residuals.iter().zip(expected_pos) {
+            for (actual, expected) in residual.pos_enu.iter().zip(expected) {
+                assert!(approx_eq!(f64, expected, *actual, epsilon = 1e-6));
+            }
+            assert!(approx_eq!(f64, 1.0, residual.vel_enu[2], epsilon = 1e-9));
+        }
+        assert!(approx_eq!(
+            f64,
+            5.0,
+            residuals[0].pos_norm(),
+            epsilon = 1e-6
+        ));
+    }
+
+    #[test]
+    fn no_residuals_across_sensor_gaps() {
+        let truth = vec![record(50, 0.0, 0.0, 0.0), record(150, 0.0, 0.0, 0.0)];
+        let sensor = vec![record(0, 1.0, 0.0, 0.0), record(100, 1.0, 0.0, 0.0)];
+
+        let residuals = compute_residuals(&truth, sensor, &[], 10, &Ellipsoid::WGS84);
+
+        assert!(residuals.is_empty());
+    }
+
+    #[test]
+    fn unsorted_sensor_solution() {
+        let truth = vec![record(50, 0.0, 0.0, 0.0)];
+        let sensor = vec![record(100, 3.0, 0.0, 0.0), record(0, 1.0, 0.0, 0.0)];
+
+        let residuals = compute_residuals(&truth, sensor, &[], 100, &Ellipsoid::WGS84);
+
+        assert_eq!(1, residuals.len());
+        assert!(approx_eq!(
+            f64,
+            2.0,
+            residuals[0].pos_enu[2],
+            epsilon = 1e-6
+        ));
+    }
+
+    #[test]
+    fn stats_per_phase() {
+        let events = vec![FlightEvent {
+            kind: EventKind::Liftoff,
+            index: 2,
+            timestamp_ns: 20,
+        }];
+        let truth: Vec<TelemetryRecord> = (0..4).map(|i| record(i * 10, 0.0, 0.0, 0.0)).collect();
+        let sensor: Vec<TelemetryRecord> = [1.0, 1.0, 3.0, 4.0]
+            .iter()
+            .enumerate()
+            .map(|(i, up)| record(i as u64 * 10, *up, 0.0, 0.0))
+            .collect();
+
+        let stats = phase_stats(&compute_residuals(
+            &truth,
+            sensor,
+            &events,
+            100,
+            &Ellipsoid::WGS84,
+        ));
+
+        assert_eq!(2, stats.len());
+        assert_eq!(FlightPhase::Prelaunch, stats[0].phase);
+        assert!(approx_eq!(f64, 1.0, stats[0].pos_rms, epsilon = 1e-6));
+        assert_eq!(FlightPhase::PoweredAscent, stats[1].phase);
+        assert_eq!(2, stats[1].samples);
+        assert!(approx_eq!(
+            f64,
+            12.5f64.sqrt(),
+            stats[1].pos_rms,
+            epsilon = 1e-6
+        ));
+        assert!(approx_eq!(f64, 4.0, stats[1].pos_max, epsilon = 1e-6));
+        assert_eq!(0.0, stats[1].vel_max);
+    }
+}
@@ -1,15 +1,133 @@
+use blue_telemetry_tui::coord::{Ellipsoid, GeoCoord, Geodesic};
+use clap::ValueEnum;
 use crossterm::event;
 use crossterm::event::{Event, KeyCode, KeyEvent, KeyEventKind};
 use ratatui::prelude::*;
 use ratatui::symbols::border;
 use ratatui::text::Span;
-use ratatui::widgets::{Axis, Block, Borders, Chart, Dataset, Gauge, Paragraph};
+use ratatui::widgets::{
+    Axis, Block, Borders, Cell, Chart, Dataset, Gauge, Paragraph, Row, Table, TableState, Tabs,
+};
 use ratatui::{symbols, DefaultTerminal, Frame};
 use std::io;
 use std::time::Duration;
 
-use crate::coord::EcefCoord;
+use crate::ballistic::Prediction;
+use crate::ccsds::DecodeReport;
+use crate::chunking::Chunk;
 use crate::csv_reader::TelemetryRecord;
+use crate::events::FlightEvent;
+use crate::geoid::Geoid;
+use crate::kalman::Estimate;
+use crate::layout::{DashboardLayout, Widget};
+use crate::orbit::{elements, OrbitalElements};
+use crate::quality::{DataQualityEvent, ValidationSummary};
+use crate::residuals::{phase_stats, PhaseStats, Residual};
+
+// 1_000_000_000 is 1 second in nanoseconds
+const NANOS_PER_SECOND: u64 = 1_000_000_000;
+// Fields of a record in the table view, followed by any sensor channels
+const TABLE_FIELDS: [&str; 20] = [
+    "t (s)",
+    "timestamp_ns",
+    "pos_x",
+    "pos_y",
+    "pos_z",
+    "vel_x",
+    "vel_y",
+    "vel_z",
+    "quat_1",
+    "quat_2",
+    "quat_3",
+    "quat_4",
+    "lat",
+    "lon",
+    "alt",
+    "vel_east",
+    "vel_north",
+    "vel_up",
+    "speed",
+    "flags",
+];
+// Resolution of the shading drawn over data gaps, in points across and up the chart
+const GAP_SHADING_STEPS: [usize; 2] = [60, 10];
+
+/// Screens below the readouts, all following the same playback
+#[derive(Debug, Default, PartialEq, Clone, Copy)]
+pub enum Tab {
+    /// Altitude and velocity with the sensor channels
+    #[default]
+    Overview,
+    /// Residuals, dead reckoning drift or the orbit
+    Charts,
+    /// Ground track around the pad
+    Map,
+    /// Every field of the records in the current chunk
+    Table,
+    /// Flight milestones and data gaps
+    Events,
+}
+
+impl Tab {
+    pub const ALL: [Tab; 5] = [
+        Tab::Overview,
+        Tab::Charts,
+        Tab::Map,
+        Tab::Table,
+        Tab::Events,
+    ];
+
+    pub fn title(self) -> &'static str {
+        match self {
+            Tab::Overview => "Overview",
+            Tab::Charts => "Charts",
+            Tab::Map => "Map",
+            Tab::Table => "Table",
+            Tab::Events => "Events",
+        }
+    }
+}
+
+/// What the charts tab shows
+#[derive(Debug, Default, PartialEq, Clone, Copy)]
+pub enum View {
+    #[default]
+    Orbit,
+    Residuals,
+    DeadReckoning,
+}
+
+/// What the displayed altitude is measured from
+#[derive(ValueEnum, Debug, Default, PartialEq, Clone, Copy)]
+pub enum AltitudeRef {
+    /// Height above the ellipsoid
+    #[default]
+    Ellipsoid,
+    /// Mean sea level, from the geoid
+    Msl,
+    /// Above the pad
+    Agl,
+}
+
+impl AltitudeRef {
+    pub fn label(self) -> &'static str {
+        match self {
+            AltitudeRef::Ellipsoid => "HAE",
+            AltitudeRef::Msl => "MSL",
+            AltitudeRef::Agl => "AGL",
+        }
+    }
+}
+
+/// How the current position is written
+#[derive(ValueEnum, Debug, Default, PartialEq, Clone, Copy)]
+pub enum GridFormat {
+    /// Latitude and longitude in degrees
+    #[default]
+    LatLon,
+    Utm,
+    Mgrs,
+}
 
 #[derive(Debug, Default)]
 pub struct App {
@@ -17,38 +135,226 @@ pub struct App {
     window_x: [f64; 2],
     window_y: [f64; 2],
     exit: bool,
-    time_chunk_duration: u64,
+    layout: DashboardLayout,
+    tab: Tab,
+    view: View,
     current_chunk: usize,
-    chunks: Vec<Vec<TelemetryRecord>>,
+    chunks: Vec<Chunk>,
     initial_time: u64,
+    // Reference for the displayed altitudes
+    ellipsoid: Ellipsoid,
+    altitude_ref: AltitudeRef,
+    geoid: Option<Geoid>,
+    // Position of the first record
+    pad: Option<GeoCoord>,
+    // Height of the pad from the samples before liftoff
+    pad_alt: Option<f64>,
     // Display fields
     avg_vel: f64,
     current_time: u64,
     current_alt: f64,
+    position: Option<GeoCoord>,
+    grid_format: GridFormat,
+    // Table view row and field cursor, and the fields left out
+    table_row: usize,
+    table_field: usize,
+    hidden_fields: Vec<usize>,
+    // Distance and bearing of the latest position from the first one, the pad
+    downrange: Option<Geodesic>,
+    // Km east and north of the pad
+    track_points: Vec<(f64, f64)>,
+    flight_events: Vec<FlightEvent>,
     altitude_points: Vec<(f64, f64)>,
+    // Geoid height in meters at each of the altitude points
+    undulation_points: Vec<(f64, f64)>,
     velocity_points: Vec<(f64, f64)>,
+    flagged_points: Vec<(f64, f64)>,
+    decode_report: Option<DecodeReport>,
+    quality_events: Vec<DataQualityEvent>,
+    validation_summary: Option<ValidationSummary>,
+    sensor_channels: Vec<String>,
+    selected_sensor: usize,
+    sensor_values: Vec<Option<f64>>,
+    sensor_points: Vec<Vec<(f64, f64)>>,
+    residuals: Vec<Residual>,
+    residual_stats: Vec<PhaseStats>,
+    dead_reckoning: Vec<Residual>,
+    filtered: Vec<Estimate>,
+    filtered_points: Vec<(f64, f64)>,
+    // Altitude and position sigma of the latest filtered estimate, in meters
+    filtered_alt: Option<(f64, f64)>,
+    // Estimates the smoother had to leave as filtered
+    unsmoothed: usize,
+    predictions: Vec<Prediction>,
+    prediction: Option<Prediction>,
+    // Timestamp and altitude the flight actually peaked at
+    actual_apogee: Option<(u64, f64)>,
+    // Elements of the latest record with its distance from the Earth's center
+    orbit: Option<(OrbitalElements, f64)>,
+    energy_to_orbit_points: Vec<(f64, f64)>,
 }
 
 impl App {
-    pub fn new(chunks: Vec<Vec<TelemetryRecord>>) -> Self {
+    pub fn new(chunks: Vec<Chunk>) -> Self {
+        // Time is shown relative to the first record rather than the first chunk
+        let initial_time = chunks
+            .iter()
+            .find_map(|chunk| chunk.records.first())
+            .map_or(0, |record| record.timestamp_ns);
+
         App {
             initial_window: [0.0, 10.0],
             window_x: [0.0, 10.0],
             window_y: [0.0, 10.0],
             exit: false,
-            // 1_000_000_000 is 1 second in nanoseconds
-            time_chunk_duration: 1_000_000_000,
+            layout: DashboardLayout::default(),
+            tab: Tab::Overview,
+            view: View::Orbit,
             current_chunk: 0,
             chunks,
-            initial_time: 0,
+            initial_time,
+            ellipsoid: Ellipsoid::WGS84,
+            altitude_ref: AltitudeRef::Ellipsoid,
+            geoid: None,
+            pad: None,
+            pad_alt: None,
             avg_vel: 0.0,
             current_time: 0,
             current_alt: 0.0,
+            position: None,
+            grid_format: GridFormat::LatLon,
+            table_row: 0,
+            table_field: 0,
+            hidden_fields: Vec::new(),
+            downrange: None,
+            track_points: Vec::new(),
+            flight_events: Vec::new(),
             altitude_points: Vec::new(),
+            undulation_points: Vec::new(),
             velocity_points: Vec::new(),
+            flagged_points: Vec::new(),
+            decode_report: None,
+            quality_events: Vec::new(),
+            validation_summary: None,
+            sensor_channels: Vec::new(),
+            selected_sensor: 0,
+            sensor_values: Vec::new(),
+            sensor_points: Vec::new(),
+            residuals: Vec::new(),
+            residual_stats: Vec::new(),
+            dead_reckoning: Vec::new(),
+            filtered: Vec::new(),
+            filtered_points: Vec::new(),
+            filtered_alt: None,
+            unsmoothed: 0,
+            predictions: Vec::new(),
+            prediction: None,
+            actual_apogee: None,
+            orbit: None,
+            energy_to_orbit_points: Vec::new(),
         }
     }
 
+    /// Show altitudes above this ellipsoid rather than WGS84
+    pub fn with_ellipsoid(mut self, ellipsoid: Ellipsoid) -> Self {
+        self.ellipsoid = ellipsoid;
+        self
+    }
+
+    /// Start with altitudes measured from `altitude_ref`
+    pub fn with_altitude_ref(mut self, altitude_ref: AltitudeRef) -> Self {
+        self.altitude_ref = altitude_ref;
+        self
+    }
+
+    /// Measure altitude above ground from `alt`, rather than the first record
+    pub fn with_pad_altitude(mut self, alt: f64) -> Self {
+        self.pad_alt = Some(alt);
+        self
+    }
+
+    /// Write the current position in `grid_format`
+    pub fn with_grid_format(mut self, grid_format: GridFormat) -> Self {
+        self.grid_format = grid_format;
+        self
+    }
+
+    /// Allow altitude above mean sea level, from this geoid
+    pub fn with_geoid(mut self, geoid: Geoid) -> Self {
+        self.geoid = Some(geoid);
+        self
+    }
+
+    /// Show the link statistics of a decoded CCSDS packet stream
+    pub fn with_decode_report(mut self, report: DecodeReport) -> Self {
+        self.decode_report = Some(report);
+        self
+    }
+
+    /// Show data gaps and other quality problems found during ingestion
+    pub fn with_quality_events(mut self, events: Vec<DataQualityEvent>) -> Self {
+        self.quality_events = events;
+        self
+    }
+
+    /// Arrange the dashboard with `layout` rather than the default
+    pub fn with_layout(mut self, layout: DashboardLayout) -> Self {
+        self.layout = layout;
+        self
+    }
+
+    /// List the detected flight milestones in the events tab
+    pub fn with_flight_events(mut self, events: Vec<FlightEvent>) -> Self {
+        self.flight_events = events;
+        self
+    }
+
+    /// Show how many records failed the plausibility checks
+    pub fn with_validation_summary(mut self, summary: ValidationSummary) -> Self {
+        self.validation_summary = Some(summary);
+        self
+    }
+
+    /// Plot the merged sensor channels below the main chart
+    pub fn with_sensor_channels(mut self, channels: Vec<String>) -> Self {
+        self.sensor_points = vec![Vec::new(); channels.len()];
+        self.sensor_values = vec![None; channels.len()];
+        self.sensor_channels = channels;
+        self
+    }
+
+    /// Compare a sensor solution against truth in the residuals view
+    pub fn with_residuals(mut self, residuals: Vec<Residual>) -> Self {
+        self.residual_stats = phase_stats(&residuals);
+        self.residuals = residuals;
+        self
+    }
+
+    /// Chart the drift of a dead-reckoned solution from truth in its own view
+    pub fn with_dead_reckoning(mut self, drift: Vec<Residual>) -> Self {
+        self.dead_reckoning = drift;
+        self
+    }
+
+    /// Plot filtered or smoothed estimates alongside the raw altitude, noting how many
+    /// the smoother couldn't improve on
+    pub fn with_filtered(mut self, estimates: Vec<Estimate>, unsmoothed: usize) -> Self {
+        self.filtered = estimates;
+        self.unsmoothed = unsmoothed;
+        self
+    }
+
+    /// Show the ballistic apogee and impact predicted during the coast next to the actual apogee
+    pub fn with_predictions(
+        mut self,
+        predictions: Vec<Prediction>,
+        actual_apogee: Option<(u64, f64)>,
+    ) -> Self {
+        self.predictions = predictions;
+        self.actual_apogee = actual_apogee;
+        self
+    }
+
     /// runs the application's main loop until the user quits
     pub fn run(&mut self, terminal: &mut DefaultTerminal) -> io::Result<()> {
         while !self.exit {
@@ -58,72 +364,239 @@ impl App {
 
             // Get next chunk of results to show
             if self.current_chunk < self.chunks.len() {
-                let chunk = self.chunks.get(self.current_chunk).unwrap();
-                let last = chunk.iter().last().take().unwrap();
+                self.next_chunk();
+            }
 
-                if self.initial_time == 0 {
-                    self.initial_time = chunk.iter().next().take().unwrap().timestamp_ns;
-                }
+            // 250 is 4/sec 4 hz. 200 is 5/sec 5 hz
+            if event::poll(Duration::from_millis(200))? {
+                self.handle_events()?;
+            }
+        }
+        Ok(())
+    }
 
-                self.current_time =
-                    (last.timestamp_ns - self.initial_time) / self.time_chunk_duration;
-
-                let ecef = EcefCoord {
-                    x: last.pos_x,
-                    y: last.pos_y,
-                    z: last.pos_z,
-                };
-                let alt: f64 = ecef.to_geo().alt.round(); // Round to whole number for nicer display
-                self.current_alt = alt;
-
-                self.altitude_points
-                    .push((self.current_chunk as f64, alt / 1000.0)); // Convert to Km for graph
-                
-                // Average the velocity over the chunk
-                let vel_sum: f64 = chunk.iter().map(|telem| 
-                    (telem.vel_x.powi(2) + telem.vel_y.powi(2) + telem.vel_z.powi(2)).sqrt()
-                )
-                .sum();
-                let avg_vel = (vel_sum / chunk.len() as f64).round();
+    /// Update the display fields from the current chunk and move on to the next one
+    #[allow(clippy::cast_precision_loss)]
+    fn next_chunk(&mut self) {
+        let chunk = &self.chunks[self.current_chunk];
+        self.current_chunk += 1;
+        // Keep the table cursor on a row of the new chunk
+        self.table_row = self.table_row.min(chunk.records.len().saturating_sub(1));
 
-                self.avg_vel = avg_vel;
-                // Todo use 2 different scales on the same chart for velocity vs km
-                self.velocity_points.push((self.current_chunk as f64, avg_vel));
+        // Empty bins from gaps in the data only move the clock forward
+        let Some(last) = chunk.records.last() else {
+            self.current_time = chunk.start_ns.saturating_sub(self.initial_time) / NANOS_PER_SECOND;
+            return;
+        };
 
-                // update window bounds for graph
-                if self.current_chunk < 240 {
-                    self.window_y[1] = (self.current_alt / 1000.0).round() + 5f64;
-                }
-                self.window_x[1] = self.current_chunk as f64 + 10f64;
+        let elapsed_ns = last.timestamp_ns.saturating_sub(self.initial_time);
+        let elapsed = self.elapsed_seconds(last.timestamp_ns);
+        let geo = last.ecef().to_geo_on(&self.ellipsoid).ok();
+        if self.pad.is_none() {
+            self.pad = self
+                .chunks
+                .iter()
+                .find_map(|chunk| chunk.records.first())
+                .and_then(|first| first.ecef().to_geo_on(&self.ellipsoid).ok());
+        }
+        if let (Some(pad), Some(geo)) = (self.pad, geo) {
+            self.downrange = Some(pad.geodesic_inverse(&geo, &self.ellipsoid));
+            self.track_points
+                .extend(self.downrange.as_ref().map(east_north));
+        }
+
+        let sensor_values = last.sensors.clone();
+
+        // Average the velocity over the chunk
+        let vel_sum: f64 = chunk.records.iter().map(TelemetryRecord::speed).sum();
+        let avg_vel = (vel_sum / chunk.records.len() as f64).round();
+        let has_flagged = chunk.records.iter().any(|record| !record.flags.is_empty());
 
-                self.current_chunk += 1;
+        self.current_time = elapsed_ns / NANOS_PER_SECOND;
+        // A position without an altitude is left off the chart and the readouts keep the last one
+        if let Some(geo) = geo {
+            let alt = geo.alt.round(); // Round to whole number for nicer display
+            self.current_alt = alt;
+            self.position = Some(geo);
+            self.altitude_points.push((elapsed, alt / 1000.0)); // Convert to Km for graph
+            if let Some(geoid) = &self.geoid {
+                self.undulation_points
+                    .push((elapsed, geoid.undulation(geo.lat, geo.lon)));
             }
+            if has_flagged {
+                self.flagged_points.push((elapsed, alt / 1000.0));
+            }
+        }
 
-            // 250 is 4/sec 4 hz. 200 is 5/sec 5 hz
-            if event::poll(Duration::from_millis(200))? {
-                self.handle_events()?;
+        // The estimate at or just before the last record of the chunk
+        let filtered_index = self
+            .filtered
+            .partition_point(|estimate| estimate.timestamp_ns <= last.timestamp_ns);
+        if let Some(estimate) = filtered_index.checked_sub(1).map(|i| self.filtered[i]) {
+            if let Ok(geo) = estimate.to_record().ecef().to_geo_on(&self.ellipsoid) {
+                self.filtered_points.push((elapsed, geo.alt / 1000.0));
+                self.filtered_alt = Some((geo.alt, estimate.pos_sigma()));
             }
         }
-        Ok(())
+
+        let pos = [last.pos_x, last.pos_y, last.pos_z];
+        let orbit = elements(&pos, &[last.vel_x, last.vel_y, last.vel_z]);
+        let radius = pos.iter().map(|c| c * c).sum::<f64>().sqrt();
+        if orbit.energy.is_finite() {
+            let energy_to_orbit = orbit.energy_to_orbit(radius) / 1e6;
+            self.energy_to_orbit_points.push((elapsed, energy_to_orbit));
+            self.orbit = Some((orbit, radius));
+        }
+
+        let prediction_index = self
+            .predictions
+            .partition_point(|prediction| prediction.from_ns <= last.timestamp_ns);
+        if let Some(index) = prediction_index.checked_sub(1) {
+            self.prediction = Some(self.predictions[index]);
+        }
+
+        for (points, value) in self.sensor_points.iter_mut().zip(&sensor_values) {
+            if let Some(value) = value {
+                points.push((elapsed, *value));
+            }
+        }
+        self.sensor_values = sensor_values;
+
+        self.avg_vel = avg_vel;
+        // Todo use 2 different scales on the same chart for velocity vs km
+        self.velocity_points.push((elapsed, avg_vel));
+
+        self.window_x[1] = elapsed.round() + 10f64;
+        self.fit_window_y();
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    fn elapsed_seconds(&self, timestamp_ns: u64) -> f64 {
+        timestamp_ns.saturating_sub(self.initial_time) as f64 / NANOS_PER_SECOND as f64
+    }
+
+    /// Data quality events that have started by the current playback time
+    fn quality_events_so_far(&self) -> impl Iterator<Item = &DataQualityEvent> {
+        let now_ns = self.initial_time + self.current_time * NANOS_PER_SECOND;
+        self.quality_events
+            .iter()
+            .filter(move |event| event.start_ns <= now_ns)
+    }
+
+    /// Points filling the chart over each data gap, up to the current time
+    #[allow(clippy::cast_possible_truncation)]
+    #[allow(clippy::cast_precision_loss)]
+    #[allow(clippy::cast_sign_loss)]
+    fn gap_shading(&self) -> Vec<(f64, f64)> {
+        let width = self.window_x[1] - self.window_x[0];
+        let height = self.window_y[1] - self.window_y[0];
+        // Nothing to shade in a chart without an extent, which also rules out NaN windows
+        if !(width > 0.0 && height > 0.0) {
+            return Vec::new();
+        }
+        let now = self.current_time as f64;
+        let (x_step, y_step) = (
+            width / GAP_SHADING_STEPS[0] as f64,
+            height / GAP_SHADING_STEPS[1] as f64,
+        );
+
+        let mut points: Vec<(f64, f64)> = Vec::new();
+        for event in self.quality_events_so_far() {
+            let start = self.elapsed_seconds(event.start_ns);
+            let end = self.elapsed_seconds(event.end_ns).min(now);
+            if end < start {
+                continue;
+            }
+            let columns = (((end - start) / x_step) as usize).min(GAP_SHADING_STEPS[0]);
+            for column in 0..=columns {
+                let x = start + column as f64 * x_step;
+                for row in 0..=GAP_SHADING_STEPS[1] {
+                    points.push((x, self.window_y[0] + row as f64 * y_step));
+                }
+            }
+        }
+        points
     }
 
     // Called to render the terminal ui
     fn draw(&self, frame: &mut Frame) {
-        let layout_rows = Layout::default()
+        let title =
+            Line::from(" Blue Origin New Shepard flight telemetry from flight NS-13 ".bold());
+        let mut instructions = vec![" Start over ".into(), "<Space>".blue().bold()];
+        instructions.extend([" Tabs ".into(), "<1-5> <Tab>".blue().bold()]);
+        if !self.residuals.is_empty() {
+            instructions.extend([" Residuals ".into(), "<R>".blue().bold()]);
+        }
+        if !self.dead_reckoning.is_empty() {
+            instructions.extend([" Dead reckoning ".into(), "<D>".blue().bold()]);
+        }
+        instructions.extend([" Orbit ".into(), "<O>".blue().bold()]);
+        instructions.extend([" Table ".into(), "<T>".blue().bold()]);
+        instructions.extend([" Altitude ".into(), "<A>".blue().bold()]);
+        instructions.extend([" Grid ".into(), "<G>".blue().bold()]);
+        instructions.extend([" Quit ".into(), "<Q> ".blue().bold()]);
+        let instructions = Line::from(instructions);
+        let block = Block::bordered()
+            .title(title.centered())
+            .title_bottom(instructions.centered())
+            .border_set(border::THICK);
+
+        // block around entire frame
+        frame.render_widget(block, frame.area());
+
+        // Rows from the top down, each split into its widgets from left to right
+        let rows = &self.layout.rows;
+        let row_areas = Layout::default()
             .direction(Direction::Vertical)
-            .constraints(vec![
-                Constraint::Percentage(5),
-                Constraint::Percentage(15),
-                Constraint::Percentage(10),
-                Constraint::Percentage(68),
-            ])
+            .constraints(rows.iter().map(|row| row.size.constraint()))
             .split(frame.area());
+        for (row, area) in rows.iter().zip(row_areas.iter()) {
+            let cell_areas = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints(row.widgets.iter().map(|cell| cell.size.constraint()))
+                .split(*area);
+            for (cell, area) in row.widgets.iter().zip(cell_areas.iter()) {
+                self.draw_widget(frame, cell.widget, *area);
+            }
+        }
+    }
 
-        let layout_top_row = Layout::default()
-            .direction(Direction::Horizontal)
-            .constraints(vec![Constraint::Percentage(20), Constraint::Percentage(80)])
-            .split(layout_rows[1]);
+    fn draw_widget(&self, frame: &mut Frame, widget: Widget, area: Rect) {
+        match widget {
+            Widget::Spacer => {}
+            Widget::Rocket => frame.render_widget(
+                Paragraph::new(" .'.\n |o|\n.'o'.\n|.-.|\n'   '").centered(),
+                area,
+            ),
+            Widget::Readouts => self.draw_readouts(frame, area),
+            Widget::Progress => self.draw_progress(frame, area),
+            Widget::Tabs => self.draw_tabs(frame, area),
+            Widget::TabContent => match self.tab {
+                Tab::Overview => self.draw_telemetry(frame, area),
+                Tab::Charts => self.draw_widget(frame, Widget::Charts, area),
+                Tab::Map => self.draw_map(frame, area),
+                Tab::Table => self.draw_table(frame, area),
+                Tab::Events => self.draw_events(frame, area),
+            },
+            Widget::Telemetry => self.draw_telemetry(frame, area),
+            Widget::Charts => match self.view {
+                View::Residuals => self.draw_residuals(frame, area),
+                View::DeadReckoning => self.draw_error_chart(
+                    frame,
+                    area,
+                    " Dead reckoning - truth drift ",
+                    &self.dead_reckoning,
+                ),
+                View::Orbit => self.draw_orbit(frame, area),
+            },
+            Widget::Map => self.draw_map(frame, area),
+            Widget::Table => self.draw_table(frame, area),
+            Widget::Events => self.draw_events(frame, area),
+        }
+    }
 
+    /// Link and data quality status, then the time, velocity, altitude and position
+    fn draw_readouts(&self, frame: &mut Frame, area: Rect) {
         let text_rows = Layout::default()
             .direction(Direction::Vertical)
             .constraints(vec![
@@ -132,53 +605,79 @@ impl App {
                 Constraint::Percentage(30),
                 Constraint::Percentage(30),
             ])
-            .split(layout_top_row[1]);
-
-        let title =
-            Line::from(" Blue Origin New Shepard flight telemetry from flight NS-13 ".bold());
-        let instructions = Line::from(vec![
-            " Start over ".into(),
-            "<Space>".blue().bold(),
-            " Quit ".into(),
-            "<Q> ".blue().bold(),
-        ]);
-        let block = Block::bordered()
-            .title(title.centered())
-            .title_bottom(instructions.centered())
-            .border_set(border::THICK);
-
-        // block around entire frame
-        frame.render_widget(block, frame.area());
-        // frame.render_widget(Block::bordered().border_set(border::THICK), layout_rows[0]);
-
-        // Row 1
-        // Render ascii rocket
-        frame.render_widget(
-            Paragraph::new(" .'.\n |o|\n.'o'.\n|.-.|\n'   '").centered(),
-            layout_top_row[0],
-        );
+            .split(area);
 
         // Show text fields
         let freq_txt = Line::from(vec!["Sim is running at 5 flight seconds per second".into()]);
         frame.render_widget(freq_txt.centered().bold(), text_rows[1]);
 
-        let time_txt = Line::from(vec![
+        let mut status_spans: Vec<Span> = Vec::new();
+        if let Some(report) = &self.decode_report {
+            status_spans.extend([
+                "Packets: ".into(),
+                report.packets.to_string().into(),
+                "  Sequence gaps: ".into(),
+                report.sequence_gaps.to_string().yellow(),
+                " (".into(),
+                report.missing_packets.to_string().into(),
+                " missing)  CRC errors: ".into(),
+                report.crc_errors.to_string().red(),
+                "  ".into(),
+            ]);
+        }
+        status_spans.extend([
+            "Data gaps: ".into(),
+            self.quality_events_so_far().count().to_string().yellow(),
+        ]);
+        if let Some(summary) = &self.validation_summary {
+            status_spans.extend([
+                "  Flagged samples: ".into(),
+                summary.failed.to_string().magenta(),
+                " (".into(),
+                summary.dropped.to_string().into(),
+                " dropped)".into(),
+            ]);
+        }
+        frame.render_widget(Line::from(status_spans).centered(), text_rows[0]);
+
+        let mut time_txt = Line::from(vec![
             "    Time: +".into(),
             self.current_time.to_string().into(),
             " seconds.    Average Velocity: ".into(),
             self.avg_vel.to_string().into(),
             " m/s ".into(),
         ]);
+        if let Some(downrange) = self.downrange {
+            // Approximate when measured on the sphere
+            time_txt.push_span(format!(
+                "   Downrange: {}{:.2} km at {:.0}°",
+                if downrange.spherical { "~" } else { "" },
+                downrange.distance / 1000.0,
+                downrange.initial_azimuth
+            ));
+        }
         frame.render_widget(time_txt.centered().bold(), text_rows[2]);
 
-        let alt_txt = Line::from(vec![
+        let offset = self.altitude_offset();
+        let mut alt_txt = Line::from(vec![
             " Altitude: ".into(),
-            self.current_alt.to_string().into(),
-            " meters".into(),
+            format!("{:.0}", self.current_alt - offset).into(),
+            format!(" meters {}", self.altitude_ref.label()).into(),
         ]);
+        if let Some((alt, sigma)) = self.filtered_alt {
+            let alt = alt - offset;
+            alt_txt.push_span(format!("    Filtered: {alt:.0} ± {sigma:.1} meters").green());
+            if self.unsmoothed > 0 {
+                alt_txt.push_span(format!(" ({} not smoothed)", self.unsmoothed).yellow());
+            }
+        }
+        if let Some(position) = self.position_text() {
+            alt_txt.push_span(format!("    Position: {position}"));
+        }
         frame.render_widget(alt_txt.centered().bold(), text_rows[3]);
+    }
 
-        // Row 2: Show progress bar
+    fn draw_progress(&self, frame: &mut Frame, area: Rect) {
         let cur_percent =
             (self.current_chunk as f32 / (self.chunks.len() as f32) * 100.0).round() as u16;
         let percent_gauge = Gauge::default()
@@ -189,9 +688,23 @@ impl App {
             )
             .gauge_style(Style::default().fg(Color::Cyan))
             .percent(cur_percent);
-        frame.render_widget(percent_gauge, layout_rows[2]);
+        frame.render_widget(percent_gauge, area);
+    }
 
-        // Row 3: Altitude chart
+    fn draw_tabs(&self, frame: &mut Frame, area: Rect) {
+        let tabs = Tabs::new(
+            Tab::ALL
+                .iter()
+                .enumerate()
+                .map(|(i, tab)| format!("{} {}", i + 1, tab.title())),
+        )
+        .select(self.tab as usize)
+        .highlight_style(Style::default().fg(Color::Cyan).bold());
+        frame.render_widget(tabs, area);
+    }
+
+    /// Altitude and velocity chart, with the selected sensor channel under it
+    fn draw_telemetry(&self, frame: &mut Frame, area: Rect) {
         // Labels for the X and Y axes
         let x_labels = vec![
             Span::styled(
@@ -227,21 +740,63 @@ impl App {
             ),
         ];
 
+        let gap_points = self.gap_shading();
+        // Sea level moves with the position, so each point is shifted by its own offset
+        let shift = |points: &[(f64, f64)]| -> Vec<(f64, f64)> {
+            points
+                .iter()
+                .map(|(t, alt)| (*t, alt - self.altitude_offset_at(*t) / 1000.0))
+                .collect()
+        };
+        let altitude_points = shift(&self.altitude_points);
+        let flagged_points = shift(&self.flagged_points);
+        let filtered_points = shift(&self.filtered_points);
+        let apogee_points: Vec<(f64, f64)> = self
+            .prediction
+            .iter()
+            .map(|p| {
+                let elapsed = self.elapsed_seconds(p.apogee_ns);
+                (
+                    elapsed,
+                    (p.apogee_alt - self.altitude_offset_at(elapsed)) / 1000.0,
+                )
+            })
+            .collect();
         let datasets = vec![
+            Dataset::default()
+                .name("Data gap")
+                .marker(symbols::Marker::Braille)
+                .style(Style::default().fg(Color::DarkGray))
+                .data(&gap_points),
             Dataset::default()
                 .name("Altitude")
                 .marker(symbols::Marker::Braille)
                 .style(Style::default().fg(Color::Cyan))
-                .data(&self.altitude_points),
+                .data(&altitude_points),
             Dataset::default()
                 .name("Velocity")
                 .marker(symbols::Marker::Braille)
                 .style(Style::default().fg(Color::Red))
                 .data(&self.velocity_points),
+            Dataset::default()
+                .name("Flagged")
+                .marker(symbols::Marker::Dot)
+                .style(Style::default().fg(Color::Magenta))
+                .data(&flagged_points),
+            Dataset::default()
+                .name("Filtered altitude")
+                .marker(symbols::Marker::Braille)
+                .style(Style::default().fg(Color::Green))
+                .data(&filtered_points),
+            Dataset::default()
+                .name("Predicted apogee")
+                .marker(symbols::Marker::Dot)
+                .style(Style::default().fg(Color::Yellow))
+                .data(&apogee_points),
         ];
 
         let chart = Chart::new(datasets)
-            .block(Block::bordered())
+            .block(Block::bordered().title(self.prediction_title()))
             .x_axis(
                 Axis::default()
                     .title("Seconds")
@@ -251,13 +806,500 @@ impl App {
             )
             .y_axis(
                 Axis::default()
-                    .title("km")
+                    .title(format!("km {}", self.altitude_ref.label()))
                     .style(Style::default().fg(Color::Gray))
                     .labels(y_labels)
                     .bounds(self.window_y),
             );
 
-        frame.render_widget(chart, layout_rows[3]);
+        if self.sensor_channels.is_empty() {
+            frame.render_widget(chart, area);
+        } else {
+            let chart_rows = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints(vec![Constraint::Percentage(60), Constraint::Percentage(40)])
+                .split(area);
+            frame.render_widget(chart, chart_rows[0]);
+            self.draw_sensor_chart(frame, chart_rows[1]);
+        }
+    }
+
+    /// Predicted apogee and impact, with the actual apogee once playback has passed it
+    fn prediction_title(&self) -> Line<'static> {
+        let Some(prediction) = self.prediction else {
+            return Line::default();
+        };
+
+        let offset = self.altitude_offset();
+        let mut spans: Vec<Span> = vec![format!(
+            " Predicted apogee {:.0} m at +{:.0} s",
+            prediction.apogee_alt - offset,
+            self.elapsed_seconds(prediction.apogee_ns)
+        )
+        .yellow()];
+        #[allow(clippy::cast_precision_loss)]
+        let now = self.current_time as f64;
+        if let Some((apogee_ns, apogee_alt)) = self.actual_apogee {
+            if self.elapsed_seconds(apogee_ns) <= now {
+                spans.push(
+                    format!(
+                        ", actual {:.0} m at +{:.0} s",
+                        apogee_alt - offset,
+                        self.elapsed_seconds(apogee_ns)
+                    )
+                    .into(),
+                );
+            }
+        }
+        if let Some(impact) = prediction.impact {
+            spans.push(format!("  Impact {:.4}, {:.4} ", impact.lat, impact.lon).yellow());
+        }
+        Line::from(spans)
+    }
+
+    /// Residual magnitudes so far next to the statistics for each flight phase
+    fn draw_residuals(&self, frame: &mut Frame, area: Rect) {
+        let columns = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints(vec![Constraint::Percentage(65), Constraint::Percentage(35)])
+            .split(area);
+
+        self.draw_error_chart(
+            frame,
+            columns[0],
+            " Sensor - truth residuals ",
+            &self.residuals,
+        );
+
+        let header = Row::new(vec![
+            "Phase", "N", "Pos RMS", "Pos max", "Vel RMS", "Vel max",
+        ])
+        .style(Style::default().add_modifier(Modifier::BOLD));
+        let rows = self.residual_stats.iter().map(|stats| {
+            Row::new(vec![
+                Cell::from(stats.phase.label()),
+                Cell::from(stats.samples.to_string()),
+                Cell::from(format!("{:.2}", stats.pos_rms)),
+                Cell::from(format!("{:.2}", stats.pos_max)),
+                Cell::from(format!("{:.3}", stats.vel_rms)),
+                Cell::from(format!("{:.3}", stats.vel_max)),
+            ])
+        });
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Length(14),
+                Constraint::Length(7),
+                Constraint::Length(8),
+                Constraint::Length(8),
+                Constraint::Length(8),
+                Constraint::Length(8),
+            ],
+        )
+        .header(header)
+        .block(Block::bordered().title(" Residuals per phase (m, m/s) "));
+        frame.render_widget(table, columns[1]);
+    }
+
+    /// Energy still needed to reach orbit next to the osculating elements of the latest record
+    fn draw_orbit(&self, frame: &mut Frame, area: Rect) {
+        let columns = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints(vec![Constraint::Percentage(65), Constraint::Percentage(35)])
+            .split(area);
+
+        let y_max = self
+            .energy_to_orbit_points
+            .iter()
+            .fold(1.0, |max: f64, (_, y)| max.max(*y))
+            * 1.1;
+        let dataset = Dataset::default()
+            .name("Energy to orbit (MJ/kg)")
+            .marker(symbols::Marker::Braille)
+            .style(Style::default().fg(Color::Cyan))
+            .data(&self.energy_to_orbit_points);
+        let chart = Chart::new(vec![dataset])
+            .block(Block::bordered().title(" Energy short of a circular orbit "))
+            .x_axis(
+                Axis::default()
+                    .title("Seconds")
+                    .style(Style::default().fg(Color::Gray))
+                    .labels(vec![
+                        Span::raw(format!("{}", self.window_x[0])),
+                        Span::raw(format!("{}", self.window_x[1])),
+                    ])
+                    .bounds(self.window_x),
+            )
+            .y_axis(
+                Axis::default()
+                    .style(Style::default().fg(Color::Gray))
+                    .labels(vec![Span::raw("0"), Span::raw(format!("{y_max:.1}"))])
+                    .bounds([0.0, y_max]),
+            );
+        frame.render_widget(chart, columns[0]);
+
+        let rows: Vec<Row> = self
+            .orbit
+            .iter()
+            .flat_map(|(orbit, radius)| {
+                [
+                    ("Energy (MJ/kg)", format!("{:.3}", orbit.energy / 1e6)),
+                    (
+                        "To orbit (MJ/kg)",
+                        format!("{:.3}", orbit.energy_to_orbit(*radius) / 1e6),
+                    ),
+                    (
+                        "Semi-major axis (km)",
+                        format!("{:.1}", orbit.semi_major_axis / 1e3),
+                    ),
+                    ("Eccentricity", format!("{:.4}", orbit.eccentricity)),
+                    ("Inclination (deg)", format!("{:.2}", orbit.inclination)),
+                    (
+                        "Apogee radius (km)",
+                        format!("{:.1}", orbit.apogee_radius / 1e3),
+                    ),
+                    (
+                        "Perigee radius (km)",
+                        format!("{:.1}", orbit.perigee_radius / 1e3),
+                    ),
+                ]
+            })
+            .map(|(name, value)| Row::new(vec![Cell::from(name), Cell::from(value)]))
+            .collect();
+        let table = Table::new(rows, [Constraint::Length(22), Constraint::Length(12)])
+            .block(Block::bordered().title(" Orbit "));
+        frame.render_widget(table, columns[1]);
+    }
+
+    /// Ground track around the pad with the predicted impact point
+    fn draw_map(&self, frame: &mut Frame, area: Rect) {
+        let impact: Vec<(f64, f64)> = self
+            .prediction
+            .and_then(|prediction| prediction.impact)
+            .zip(self.pad)
+            .map(|(impact, pad)| pad.geodesic_inverse(&impact, &self.ellipsoid))
+            .as_ref()
+            .map(east_north)
+            .into_iter()
+            .collect();
+        let pad = [(0.0, 0.0)];
+        // Square bounds around the pad that fit everything, at least a km across
+        let extent = self
+            .track_points
+            .iter()
+            .chain(&impact)
+            .fold(0.5, |max: f64, (east, north)| {
+                max.max(east.abs()).max(north.abs())
+            })
+            * 1.1;
+        let bounds = [-extent, extent];
+        let labels = || {
+            vec![
+                Span::raw(format!("{:.1}", -extent)),
+                Span::raw("0"),
+                Span::raw(format!("{extent:.1}")),
+            ]
+        };
+
+        let datasets = vec![
+            Dataset::default()
+                .name("Track")
+                .marker(symbols::Marker::Braille)
+                .style(Style::default().fg(Color::Cyan))
+                .data(&self.track_points),
+            Dataset::default()
+                .name("Pad")
+                .marker(symbols::Marker::Block)
+                .style(Style::default().fg(Color::White))
+                .data(&pad),
+            Dataset::default()
+                .name("Predicted impact")
+                .marker(symbols::Marker::Block)
+                .style(Style::default().fg(Color::Yellow))
+                .data(&impact),
+        ];
+        let chart = Chart::new(datasets)
+            .block(Block::bordered().title(" Ground track from the pad "))
+            .x_axis(
+                Axis::default()
+                    .title("km east")
+                    .style(Style::default().fg(Color::Gray))
+                    .labels(labels())
+                    .bounds(bounds),
+            )
+            .y_axis(
+                Axis::default()
+                    .title("km north")
+                    .style(Style::default().fg(Color::Gray))
+                    .labels(labels())
+                    .bounds(bounds),
+            );
+        frame.render_widget(chart, area);
+    }
+
+    /// Flight milestones and data gaps in time order, the ones still to come dimmed
+    fn draw_events(&self, frame: &mut Frame, area: Rect) {
+        let milestones = self
+            .flight_events
+            .iter()
+            .map(|event| (event.timestamp_ns, event.kind.label(), String::new()));
+        let gaps = self.quality_events.iter().map(|event| {
+            #[allow(clippy::cast_precision_loss)]
+            let duration = (event.end_ns - event.start_ns) as f64 / 1e9;
+            (event.start_ns, "Data gap", format!("{duration:.1}"))
+        });
+        let mut events: Vec<(u64, &str, String)> = milestones.chain(gaps).collect();
+        events.sort_by_key(|(timestamp_ns, _, _)| *timestamp_ns);
+
+        #[allow(clippy::cast_precision_loss)]
+        let now = self.current_time as f64;
+        let header = Row::new(vec!["Event", "Time (s)", "Duration (s)"])
+            .style(Style::default().add_modifier(Modifier::BOLD));
+        let rows = events.into_iter().map(|(timestamp_ns, label, duration)| {
+            let elapsed = self.elapsed_seconds(timestamp_ns);
+            let style = if elapsed <= now {
+                Style::default()
+            } else {
+                Style::default().fg(Color::DarkGray)
+            };
+            Row::new(vec![label.to_string(), format!("+{elapsed:.1}"), duration]).style(style)
+        });
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Length(12),
+                Constraint::Length(10),
+                Constraint::Length(12),
+            ],
+        )
+        .header(header)
+        .block(Block::bordered().title(" Events "));
+        frame.render_widget(table, area);
+    }
+
+    /// Field list with the ones shown ticked, next to the records of the current chunk
+    fn draw_table(&self, frame: &mut Frame, area: Rect) {
+        let columns = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints(vec![Constraint::Length(22), Constraint::Min(0)])
+            .split(area);
+
+        let fields = self.table_fields();
+        let field_rows = fields.iter().enumerate().map(|(i, name)| {
+            let shown = if self.hidden_fields.contains(&i) {
+                "[ ]"
+            } else {
+                "[x]"
+            };
+            Row::new(vec![format!("{shown} {name}")])
+        });
+        let field_table = Table::new(field_rows, [Constraint::Min(0)])
+            .highlight_style(Style::default().reversed())
+            .block(Block::bordered().title(" Fields ←→ <Enter> "));
+        let mut field_state = TableState::default().with_selected(Some(self.table_field));
+        frame.render_stateful_widget(field_table, columns[0], &mut field_state);
+
+        let shown: Vec<usize> = (0..fields.len())
+            .filter(|i| !self.hidden_fields.contains(i))
+            .collect();
+        let records = self.current_records();
+        let values: Vec<Vec<String>> = records
+            .iter()
+            .map(|record| self.table_values(record))
+            .collect();
+        // Wide enough for the header and every value
+        let widths = shown.iter().map(|i| {
+            let width = values
+                .iter()
+                .map(|row| row[*i].len())
+                .chain([fields[*i].len()])
+                .max()
+                .unwrap_or_default();
+            Constraint::Length(u16::try_from(width).unwrap_or(u16::MAX))
+        });
+        let header = Row::new(shown.iter().map(|i| fields[*i].clone()))
+            .style(Style::default().add_modifier(Modifier::BOLD));
+        // Flagged records stand out in the same color as on the chart
+        let rows = values.iter().zip(records).map(|(row, record)| {
+            let style = if record.flags.is_empty() {
+                Style::default()
+            } else {
+                Style::default().fg(Color::Magenta)
+            };
+            Row::new(shown.iter().map(|i| row[*i].clone())).style(style)
+        });
+        let table = Table::new(rows, widths)
+            .header(header)
+            .highlight_style(Style::default().reversed())
+            .block(Block::bordered().title(format!(" {} records ↑↓ ", records.len())));
+        let selected = self.table_row.min(records.len().saturating_sub(1));
+        let mut state = TableState::default().with_selected(Some(selected));
+        frame.render_stateful_widget(table, columns[1], &mut state);
+    }
+
+    fn table_fields(&self) -> Vec<String> {
+        TABLE_FIELDS
+            .iter()
+            .map(ToString::to_string)
+            .chain(self.sensor_channels.iter().cloned())
+            .collect()
+    }
+
+    /// Raw values at full precision, then the derived ones
+    fn table_values(&self, record: &TelemetryRecord) -> Vec<String> {
+        let geo = record.ecef().to_geo_on(&self.ellipsoid).ok();
+        let enu = geo.map(|geo| geo.ecef_to_enu(record.vel_x, record.vel_y, record.vel_z));
+        let derived = |value: Option<f64>, decimals: usize| {
+            value.map_or_else(|| "-".to_string(), |value| format!("{value:.decimals$}"))
+        };
+        let flags = record.flags.labels();
+
+        let mut values = vec![
+            format!("{:.3}", self.elapsed_seconds(record.timestamp_ns)),
+            record.timestamp_ns.to_string(),
+        ];
+        values.extend(
+            [record.pos_x, record.pos_y, record.pos_z]
+                .into_iter()
+                .chain([record.vel_x, record.vel_y, record.vel_z])
+                .chain(record.quat)
+                .map(|value| value.to_string()),
+        );
+        values.extend([
+            derived(geo.map(|geo| geo.lat), 8),
+            derived(geo.map(|geo| geo.lon), 8),
+            derived(geo.map(|geo| geo.alt), 3),
+            derived(enu.map(|enu| enu[0]), 3),
+            derived(enu.map(|enu| enu[1]), 3),
+            derived(enu.map(|enu| enu[2]), 3),
+            format!("{:.3}", record.speed()),
+            if flags.is_empty() {
+                "-".to_string()
+            } else {
+                flags.join(", ")
+            },
+        ]);
+        values.extend(
+            (0..self.sensor_channels.len())
+                .map(|channel| record.sensors.get(channel).copied().flatten())
+                .map(|value| value.map_or_else(|| "-".to_string(), |value| value.to_string())),
+        );
+        values
+    }
+
+    /// Records of the chunk played last
+    fn current_records(&self) -> &[TelemetryRecord] {
+        self.current_chunk
+            .checked_sub(1)
+            .map_or(&[], |chunk| &self.chunks[chunk].records)
+    }
+
+    /// Position and velocity error magnitudes up to the current time
+    fn draw_error_chart(&self, frame: &mut Frame, area: Rect, title: &str, errors: &[Residual]) {
+        #[allow(clippy::cast_precision_loss)]
+        let now = self.current_time as f64;
+        let so_far: Vec<&Residual> = errors
+            .iter()
+            .take_while(|r| self.elapsed_seconds(r.timestamp_ns) <= now)
+            .collect();
+        let pos_points: Vec<(f64, f64)> = so_far
+            .iter()
+            .map(|r| (self.elapsed_seconds(r.timestamp_ns), r.pos_norm()))
+            .collect();
+        let vel_points: Vec<(f64, f64)> = so_far
+            .iter()
+            .map(|r| (self.elapsed_seconds(r.timestamp_ns), r.vel_norm()))
+            .collect();
+        let y_max = pos_points
+            .iter()
+            .chain(&vel_points)
+            .fold(1.0, |max: f64, (_, y)| max.max(*y))
+            * 1.1;
+
+        let datasets = vec![
+            Dataset::default()
+                .name("Position (m)")
+                .marker(symbols::Marker::Braille)
+                .style(Style::default().fg(Color::Cyan))
+                .data(&pos_points),
+            Dataset::default()
+                .name("Velocity (m/s)")
+                .marker(symbols::Marker::Braille)
+                .style(Style::default().fg(Color::Red))
+                .data(&vel_points),
+        ];
+        let chart = Chart::new(datasets)
+            .block(Block::bordered().title(title))
+            .x_axis(
+                Axis::default()
+                    .title("Seconds")
+                    .style(Style::default().fg(Color::Gray))
+                    .labels(vec![
+                        Span::raw(format!("{}", self.window_x[0])),
+                        Span::raw(format!("{}", self.window_x[1])),
+                    ])
+                    .bounds(self.window_x),
+            )
+            .y_axis(
+                Axis::default()
+                    .style(Style::default().fg(Color::Gray))
+                    .labels(vec![Span::raw("0"), Span::raw(format!("{y_max:.2}"))])
+                    .bounds([0.0, y_max]),
+            );
+        frame.render_widget(chart, area);
+    }
+
+    /// Chart of the selected sensor channel on the same time axis as the main chart
+    fn draw_sensor_chart(&self, frame: &mut Frame, area: Rect) {
+        let name = &self.sensor_channels[self.selected_sensor];
+        let points = &self.sensor_points[self.selected_sensor];
+        let value = self.sensor_values[self.selected_sensor]
+            .map_or("no data".to_string(), |value| format!("{value:.3}"));
+
+        // Fit the y axis to the data seen so far
+        let (min, max) = points
+            .iter()
+            .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), (_, y)| {
+                (min.min(*y), max.max(*y))
+            });
+        let (min, max) = if min <= max { (min, max) } else { (0.0, 1.0) };
+        let margin = ((max - min) * 0.05).max(1e-6);
+        let bounds = [min - margin, max + margin];
+
+        let title = Line::from(vec![
+            format!(" {name} = {value} ").bold(),
+            format!(
+                "({}/{}) ",
+                self.selected_sensor + 1,
+                self.sensor_channels.len()
+            )
+            .into(),
+            "Next channel ".into(),
+            "<S> ".blue().bold(),
+        ]);
+        let dataset = Dataset::default()
+            .name(name.as_str())
+            .marker(symbols::Marker::Braille)
+            .style(Style::default().fg(Color::Yellow))
+            .data(points);
+        let chart = Chart::new(vec![dataset])
+            .block(Block::bordered().title(title))
+            .x_axis(
+                Axis::default()
+                    .style(Style::default().fg(Color::Gray))
+                    .bounds(self.window_x),
+            )
+            .y_axis(
+                Axis::default()
+                    .style(Style::default().fg(Color::Gray))
+                    .labels(vec![
+                        Span::raw(format!("{:.2}", bounds[0])),
+                        Span::raw(format!("{:.2}", bounds[1])),
+                    ])
+                    .bounds(bounds),
+            );
+
+        frame.render_widget(chart, area);
     }
 
     fn handle_events(&mut self) -> io::Result<()> {
@@ -275,8 +1317,37 @@ impl App {
     fn handle_key_event(&mut self, key_event: KeyEvent) {
         match key_event.code {
             KeyCode::Char('q') => self.exit(),
-            KeyCode::Backspace => println!("You pressed Backspace"),
             KeyCode::Char(' ') => self.reset_sim(),
+            KeyCode::Char('s') => self.next_sensor(),
+            KeyCode::Char('r') => self.toggle_view(View::Residuals),
+            KeyCode::Char('d') => self.toggle_view(View::DeadReckoning),
+            KeyCode::Char('o') => self.toggle_view(View::Orbit),
+            KeyCode::Char('a') => self.next_altitude_ref(),
+            KeyCode::Char('g') => self.next_grid_format(),
+            KeyCode::Char('t') => self.toggle_tab(Tab::Table),
+            KeyCode::Char(key @ '1'..='5') => {
+                let digit = key.to_digit(10).unwrap_or(1) as usize;
+                self.tab = Tab::ALL[digit - 1];
+            }
+            KeyCode::Tab => self.tab = Tab::ALL[(self.tab as usize + 1) % Tab::ALL.len()],
+            KeyCode::BackTab => {
+                self.tab = Tab::ALL[(self.tab as usize + Tab::ALL.len() - 1) % Tab::ALL.len()];
+            }
+            KeyCode::Up if self.tab == Tab::Table => {
+                self.table_row = self.table_row.saturating_sub(1);
+            }
+            KeyCode::Down if self.tab == Tab::Table => {
+                let last = self.current_records().len().saturating_sub(1);
+                self.table_row = (self.table_row + 1).min(last);
+            }
+            KeyCode::Left if self.tab == Tab::Table => {
+                self.table_field = self.table_field.saturating_sub(1);
+            }
+            KeyCode::Right if self.tab == Tab::Table => {
+                let last = self.table_fields().len() - 1;
+                self.table_field = (self.table_field + 1).min(last);
+            }
+            KeyCode::Enter if self.tab == Tab::Table => self.toggle_field(),
             _ => {}
         }
     }
@@ -284,20 +1355,178 @@ impl App {
     fn exit(&mut self) {
         self.exit = true;
     }
+    fn toggle_view(&mut self, view: View) {
+        //! Show `view` in the charts tab if it has data, or go back to the overview if it is
+        //! already shown
+        let available = match view {
+            View::Residuals => !self.residuals.is_empty(),
+            View::DeadReckoning => !self.dead_reckoning.is_empty(),
+            View::Orbit => true,
+        };
+        if self.tab == Tab::Charts && self.view == view {
+            self.tab = Tab::Overview;
+        } else if available {
+            self.tab = Tab::Charts;
+            self.view = view;
+        }
+    }
+    fn toggle_tab(&mut self, tab: Tab) {
+        self.tab = if self.tab == tab { Tab::Overview } else { tab };
+    }
+    fn toggle_field(&mut self) {
+        //! Show or hide the field under the cursor in the table view
+        if let Some(i) = self
+            .hidden_fields
+            .iter()
+            .position(|i| *i == self.table_field)
+        {
+            self.hidden_fields.remove(i);
+        } else {
+            self.hidden_fields.push(self.table_field);
+        }
+    }
+    fn next_sensor(&mut self) {
+        if !self.sensor_channels.is_empty() {
+            self.selected_sensor = (self.selected_sensor + 1) % self.sensor_channels.len();
+        }
+    }
+    fn next_altitude_ref(&mut self) {
+        //! Cycle through the altitude references, sea level needs a geoid
+        self.altitude_ref = match self.altitude_ref {
+            AltitudeRef::Ellipsoid if self.geoid.is_some() => AltitudeRef::Msl,
+            AltitudeRef::Ellipsoid | AltitudeRef::Msl => AltitudeRef::Agl,
+            AltitudeRef::Agl => AltitudeRef::Ellipsoid,
+        };
+        self.fit_window_y();
+    }
+
+    /// Height in meters of the altitude reference above the ellipsoid at the latest position
+    fn altitude_offset(&self) -> f64 {
+        self.altitude_offset_at(f64::INFINITY)
+    }
+
+    /// Height in meters of the altitude reference above the ellipsoid `elapsed` seconds in,
+    /// sea level is taken from the last position at or before then
+    fn altitude_offset_at(&self, elapsed: f64) -> f64 {
+        match self.altitude_ref {
+            AltitudeRef::Ellipsoid => 0.0,
+            AltitudeRef::Msl => {
+                let index = self
+                    .undulation_points
+                    .partition_point(|(t, _)| *t <= elapsed);
+                self.undulation_points
+                    .get(index.saturating_sub(1))
+                    .map_or(0.0, |(_, undulation)| *undulation)
+            }
+            AltitudeRef::Agl => self.pad_alt.or(self.pad.map(|pad| pad.alt)).unwrap_or(0.0),
+        }
+    }
+
+    /// Size the altitude axis to the displayed altitude, it stops growing after 240 seconds
+    fn fit_window_y(&mut self) {
+        let index = self
+            .altitude_points
+            .partition_point(|(elapsed, _)| *elapsed < 240.0);
+        if let Some((elapsed, alt)) = index.checked_sub(1).map(|i| self.altitude_points[i]) {
+            self.window_y[1] = (alt - self.altitude_offset_at(elapsed) / 1000.0).round() + 5f64;
+        }
+    }
+
+    fn next_grid_format(&mut self) {
+        self.grid_format = match self.grid_format {
+            GridFormat::LatLon => GridFormat::Utm,
+            GridFormat::Utm => GridFormat::Mgrs,
+            GridFormat::Mgrs => GridFormat::LatLon,
+        };
+    }
+
+    /// The latest position in the selected format, UTM and MGRS stop short of the poles
+    fn position_text(&self) -> Option<String> {
+        let position = self.position?;
+        match self.grid_format {
+            GridFormat::LatLon => Some(format!("{:.5}°, {:.5}°", position.lat, position.lon)),
+            GridFormat::Utm => position.to_utm(&self.ellipsoid).map(|utm| utm.to_string()),
+            GridFormat::Mgrs => position
+                .to_mgrs(&self.ellipsoid)
+                .map(|mgrs| mgrs.to_string()),
+        }
+    }
+
     fn reset_sim(&mut self) {
-        //! Reset the sim default values
+        //! Reset the sim default values and clear what the playback has shown so far
         self.window_x = self.initial_window;
         self.window_y = self.initial_window;
         self.current_chunk = 0;
+        self.pad = None;
+        self.avg_vel = 0.0;
+        self.current_time = 0;
+        self.current_alt = 0.0;
+        self.position = None;
+        self.table_row = 0;
+        self.downrange = None;
+        self.track_points.clear();
+        self.altitude_points.clear();
+        self.undulation_points.clear();
+        self.velocity_points.clear();
+        self.flagged_points.clear();
+        self.sensor_values.fill(None);
+        for points in &mut self.sensor_points {
+            points.clear();
+        }
+        self.filtered_points.clear();
+        self.filtered_alt = None;
+        self.prediction = None;
+        self.orbit = None;
+        self.energy_to_orbit_points.clear();
     }
 }
 
+/// Kilometers east and north along the start of a geodesic from the pad
+fn east_north(geodesic: &Geodesic) -> (f64, f64) {
+    let (sin, cos) = geodesic.initial_azimuth.to_radians().sin_cos();
+    (
+        geodesic.distance * sin / 1000.0,
+        geodesic.distance * cos / 1000.0,
+    )
+}
+
 /***************************
            Tests
 ***************************/
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::ballistic::predict;
+    use crate::chunking::{chunk_records, ChunkStrategy};
+    use crate::csv_reader::read_csv;
+    use crate::kalman::{smooth, KalmanConfig};
+    use crate::merge::{merge_sensors, read_sensor_csv, JoinMethod};
+    use crate::quality::{detect_gaps, QualityFlags};
+    use crate::residuals::compute_residuals;
+    use float_cmp::approx_eq;
+    use ratatui::backend::TestBackend;
+    use ratatui::Terminal;
+
+    // Text on the test terminal, one line per row
+    fn screen(terminal: &Terminal<TestBackend>) -> String {
+        let buffer = terminal.backend().buffer();
+        let width = buffer.area.width as usize;
+        buffer
+            .content
+            .chunks(width)
+            .map(|row| row.iter().map(|cell| cell.symbol()).collect::<String>())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    fn test_app() -> App {
+        let records = read_csv("data/test.csv").unwrap();
+        let gaps = detect_gaps(&records, 3.0);
+        let strategy = ChunkStrategy::AlignedBins {
+            duration_ns: NANOS_PER_SECOND,
+        };
+        App::new(chunk_records(records, strategy).unwrap()).with_quality_events(gaps)
+    }
 
     #[test]
     fn handle_key_event() -> io::Result<()> {
@@ -312,4 +1541,448 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn restart_playback() {
+        let mut app =
+            test_app().with_geoid(Geoid::parse("-90 90 0 360 180 360 30 30 30 30").unwrap());
+        app.next_altitude_ref();
+        let mut terminal = Terminal::new(TestBackend::new(120, 40)).unwrap();
+        let mut play = |app: &mut App| {
+            while app.altitude_points.len() < 3 {
+                app.next_chunk();
+            }
+            terminal.draw(|frame| app.draw(frame)).unwrap();
+            screen(&terminal)
+        };
+        let first_run = play(&mut app);
+        let (altitude_points, window_y) = (app.altitude_points.clone(), app.window_y);
+
+        app.handle_key_event(KeyCode::Char(' ').into());
+        assert!(app.altitude_points.is_empty() && app.undulation_points.is_empty());
+        assert_eq!((None, None), (app.pad, app.downrange));
+        assert_eq!(app.initial_window, app.window_y);
+
+        // The replay draws the same chart instead of going back to the start of the old one
+        let second_run = play(&mut app);
+        assert_eq!(altitude_points, app.altitude_points);
+        assert_eq!(3, app.undulation_points.len());
+        assert_eq!(window_y, app.window_y);
+        assert_eq!(30.0, app.altitude_offset());
+        assert_eq!(first_run, second_run);
+    }
+
+    #[test]
+    fn play_through_gaps() {
+        let mut app = test_app();
+        let mut terminal = Terminal::new(TestBackend::new(120, 40)).unwrap();
+
+        while app.current_chunk < app.chunks.len() {
+            app.next_chunk();
+            terminal.draw(|frame| app.draw(frame)).unwrap();
+        }
+
+        // Empty bins in the gaps don't add points to the chart
+        assert_eq!(4, app.altitude_points.len());
+        assert_eq!(100, app.current_time);
+        assert_eq!(2, app.quality_events_so_far().count());
+        assert!(screen(&terminal).contains("Data gaps: 2"));
+        let shading = app.gap_shading();
+        assert!(!shading.is_empty());
+        assert!(shading.len() <= 2 * (GAP_SHADING_STEPS[0] + 1) * (GAP_SHADING_STEPS[1] + 1));
+        assert!(shading.iter().all(|(_, y)| *y <= app.window_y[1] + 1e-9));
+        // A flat window would otherwise never step up the chart
+        app.window_y = [1.0, 1.0];
+        assert!(app.gap_shading().is_empty());
+    }
+
+    #[test]
+    fn skip_positions_without_altitude() {
+        let mut records = read_csv("data/test.csv").unwrap();
+        records[2].pos_x = f64::NAN;
+        let strategy = ChunkStrategy::Count { size: 1 };
+        let mut app = App::new(chunk_records(records, strategy).unwrap());
+        let mut terminal = Terminal::new(TestBackend::new(120, 40)).unwrap();
+
+        app.next_chunk();
+        app.next_chunk();
+        let (alt, window_y) = (app.current_alt, app.window_y);
+        app.next_chunk();
+        terminal.draw(|frame| app.draw(frame)).unwrap();
+
+        assert_eq!(2, app.altitude_points.len());
+        assert!(app.altitude_points.iter().all(|(_, alt)| alt.is_finite()));
+        assert_eq!((alt, window_y), (app.current_alt, app.window_y));
+        assert!(screen(&terminal).contains(&format!("Altitude: {alt:.0} meters HAE")));
+        app.next_chunk();
+        assert_eq!(3, app.altitude_points.len());
+    }
+
+    #[test]
+    fn sensor_chart() {
+        let mut records = read_csv("data/test.csv").unwrap();
+        let sensor = read_sensor_csv("data/test.csv").unwrap();
+        let channels = merge_sensors(
+            &mut records,
+            &[sensor],
+            JoinMethod::Nearest { tolerance_ns: 0 },
+        );
+        let strategy = ChunkStrategy::Count { size: 2 };
+        let mut app =
+            App::new(chunk_records(records, strategy).unwrap()).with_sensor_channels(channels);
+        let mut terminal = Terminal::new(TestBackend::new(120, 40)).unwrap();
+
+        app.next_chunk();
+        app.handle_key_event(KeyCode::Char('s').into());
+        terminal.draw(|frame| app.draw(frame)).unwrap();
+
+        assert_eq!(1, app.selected_sensor);
+        assert_eq!(1, app.sensor_points[1].len());
+        assert_eq!(Some(-5268929.31643027), app.sensor_values[1]);
+        assert!(
+            screen(&terminal).contains(" test.truth_pos_CON_ECEF_ECEF_M_2 = -5268929.316 (2/10)")
+        );
+    }
+
+    #[test]
+    fn residuals_view() {
+        let truth = read_csv("data/test.csv").unwrap();
+        // A sensor 5 m and 0.5 m/s off truth throughout
+        let sensor: Vec<TelemetryRecord> = truth
+            .iter()
+            .map(|r| TelemetryRecord {
+                pos_x: r.pos_x + 3.0,
+                pos_y: r.pos_y + 4.0,
+                vel_z: r.vel_z + 0.5,
+                ..r.clone()
+            })
+            .collect();
+        let residuals = compute_residuals(&truth, sensor, &[], 0, &Ellipsoid::WGS84);
+        let mut app = test_app().with_residuals(residuals);
+        let mut terminal = Terminal::new(TestBackend::new(120, 40)).unwrap();
+
+        app.handle_key_event(KeyCode::Char('r').into());
+        assert_eq!((Tab::Charts, View::Residuals), (app.tab, app.view));
+        app.next_chunk();
+        terminal.draw(|frame| app.draw(frame)).unwrap();
+
+        assert_eq!(1, app.residual_stats.len());
+        let stats = app.residual_stats[0];
+        assert_eq!(6, stats.samples);
+        assert!((stats.pos_rms - 5.0).abs() < 1e-6);
+        assert!((stats.pos_max - 5.0).abs() < 1e-6);
+        assert!((stats.vel_max - 0.5).abs() < 1e-9);
+        let screen = screen(&terminal);
+        assert!(screen.contains(" Sensor - truth residuals "));
+        // The chart is scaled to the 5 m residual so far
+        assert!(screen.contains("│5.50│"));
+        let row = screen.lines().find(|line| line.contains("Prelau")).unwrap();
+        assert!(row.contains("6      5.00   5.00  0.500  0.500"));
+
+        app.handle_key_event(KeyCode::Char('r').into());
+        assert_eq!(Tab::Overview, app.tab);
+    }
+
+    #[test]
+    fn filtered_overlay() {
+        let records = read_csv("data/test.csv").unwrap();
+        let (estimates, unsmoothed) = smooth(&records, &KalmanConfig::default());
+        let mut app = test_app().with_filtered(estimates, unsmoothed);
+        let mut terminal = Terminal::new(TestBackend::new(120, 40)).unwrap();
+
+        app.next_chunk();
+        terminal.draw(|frame| app.draw(frame)).unwrap();
+
+        assert_eq!(1, app.filtered_points.len());
+        let (alt, sigma) = app.filtered_alt.unwrap();
+        assert!((alt - app.current_alt).abs() < 10.0);
+        // The smoother can't be more certain than a 5 m position fix
+        assert!(sigma > 0.0 && sigma < 10.0);
+        assert!(screen(&terminal).contains(&format!("Filtered: {alt:.0} ± {sigma:.1} meters")));
+        assert!(!screen(&terminal).contains("not smoothed"));
+    }
+
+    #[test]
+    fn dead_reckoning_view() {
+        let truth = read_csv("data/test.csv").unwrap();
+        // Drifting another 10 m further from truth with every record
+        let dead_reckoned: Vec<TelemetryRecord> = truth
+            .iter()
+            .zip(0..)
+            .map(|(r, i)| TelemetryRecord {
+                pos_x: r.pos_x + f64::from(i) * 10.0,
+                ..r.clone()
+            })
+            .collect();
+        let drift = compute_residuals(&truth, dead_reckoned, &[], 0, &Ellipsoid::WGS84);
+        let mut app = test_app().with_dead_reckoning(drift);
+        let mut terminal = Terminal::new(TestBackend::new(120, 40)).unwrap();
+
+        // No residuals were given, so only the dead reckoning view can be shown
+        app.handle_key_event(KeyCode::Char('r').into());
+        assert_eq!(Tab::Overview, app.tab);
+        app.handle_key_event(KeyCode::Char('d').into());
+        assert_eq!((Tab::Charts, View::DeadReckoning), (app.tab, app.view));
+
+        app.next_chunk();
+        terminal.draw(|frame| app.draw(frame)).unwrap();
+        assert!(screen(&terminal).contains(" Dead reckoning - truth drift "));
+        // No drift yet, so the chart keeps its smallest scale
+        assert!(screen(&terminal).contains("│1.10│"));
+
+        while app.current_chunk < app.chunks.len() {
+            app.next_chunk();
+        }
+        terminal.draw(|frame| app.draw(frame)).unwrap();
+        // The last record is past the final whole second, so the chart tops out at 40 m
+        assert!(screen(&terminal).contains("│44.00│"));
+
+        app.handle_key_event(KeyCode::Char('d').into());
+        assert_eq!(Tab::Overview, app.tab);
+    }
+
+    #[test]
+    fn apogee_prediction() {
+        let records = read_csv("data/test.csv").unwrap();
+        let predictions = vec![predict(&records[0], 0.0, None, &Ellipsoid::WGS84)];
+        let actual_apogee = Some((records[0].timestamp_ns, 1_000.0));
+        let mut app = test_app().with_predictions(predictions, actual_apogee);
+        let mut terminal = Terminal::new(TestBackend::new(120, 40)).unwrap();
+
+        assert_eq!(Line::default(), app.prediction_title());
+        app.next_chunk();
+        terminal.draw(|frame| app.draw(frame)).unwrap();
+
+        // Sitting still on the pad, the predicted apogee is where it already is
+        let prediction = app.prediction.unwrap();
+        assert!((prediction.apogee_alt - app.current_alt).abs() < 1.0);
+        let title = app.prediction_title().to_string();
+        assert!(title.starts_with(&format!(
+            " Predicted apogee {:.0} m at +0 s",
+            prediction.apogee_alt
+        )));
+        assert!(title.contains("actual 1000 m at +0 s"));
+        assert!(screen(&terminal).contains(title.trim_end()));
+    }
+
+    #[test]
+    fn orbit_panel() {
+        let mut app = test_app();
+        let mut terminal = Terminal::new(TestBackend::new(120, 40)).unwrap();
+
+        app.handle_key_event(KeyCode::Char('o').into());
+        app.next_chunk();
+        terminal.draw(|frame| app.draw(frame)).unwrap();
+
+        // Sitting on the pad is nowhere near orbit
+        let (orbit, radius) = app.orbit.unwrap();
+        assert!(orbit.eccentricity > 0.99);
+        assert!(orbit.energy_to_orbit(radius) > 3e7);
+        assert_eq!(1, app.energy_to_orbit_points.len());
+        let screen = screen(&terminal);
+        let row = screen
+            .lines()
+            .find(|line| line.contains("Eccentricity"))
+            .unwrap();
+        assert!(row.contains(&format!("{:.4}", orbit.eccentricity)));
+        // The chart is scaled to the energy still needed
+        let y_max = orbit.energy_to_orbit(radius) / 1e6 * 1.1;
+        assert!(screen.contains(&format!("│{y_max:.1}│")));
+    }
+
+    #[test]
+    fn downrange_from_pad() {
+        let mut app = test_app();
+        assert_eq!(None, app.downrange);
+
+        app.next_chunk();
+        app.next_chunk();
+
+        // The second record is 0.06 mm west by north of the first, 5.98e-5 m at 277.02° from the
+        // east and north components of the difference of their ECEF positions
+        let downrange = app.downrange.unwrap();
+        assert!(!downrange.spherical);
+        assert!(approx_eq!(f64, 5.98e-5, downrange.distance, epsilon = 2e-7));
+        assert!(approx_eq!(
+            f64,
+            277.02,
+            downrange.initial_azimuth,
+            epsilon = 0.01
+        ));
+    }
+
+    #[test]
+    fn altitude_ref_toggle() {
+        let mut app = test_app();
+        app.next_chunk();
+
+        // Sea level is skipped without a geoid
+        app.next_altitude_ref();
+        assert_eq!(AltitudeRef::Agl, app.altitude_ref);
+        assert_eq!(app.pad.unwrap().alt, app.altitude_offset());
+        app.next_altitude_ref();
+        assert_eq!(AltitudeRef::Ellipsoid, app.altitude_ref);
+        assert_eq!(0.0, app.altitude_offset());
+
+        let mut app =
+            test_app().with_geoid(Geoid::parse("-90 90 0 360 180 360 30 30 30 30").unwrap());
+        app.next_chunk();
+        app.next_altitude_ref();
+        assert_eq!(AltitudeRef::Msl, app.altitude_ref);
+        assert_eq!(30.0, app.altitude_offset());
+    }
+
+    #[test]
+    fn sea_level_per_point() {
+        let mut app =
+            test_app().with_geoid(Geoid::parse("-90 90 0 360 180 360 30 30 30 30").unwrap());
+        while app.altitude_points.len() < 2 {
+            app.next_chunk();
+        }
+        assert_eq!(2, app.undulation_points.len());
+        let hae_window = (app.current_alt / 1000.0).round() + 5.0;
+        assert_eq!(hae_window, app.window_y[1]);
+
+        // As if the first point had been over lower sea level, it keeps its own offset
+        app.undulation_points[0].1 = -1970.0;
+        app.next_altitude_ref();
+        let (first, last) = (app.altitude_points[0], app.altitude_points[1]);
+        assert_eq!(-1970.0, app.altitude_offset_at(first.0));
+        assert_eq!(30.0, app.altitude_offset_at(last.0));
+        assert_eq!(30.0, app.altitude_offset());
+
+        // The axis follows the altitude above the reference
+        let msl_window = ((app.current_alt - 30.0) / 1000.0).round() + 5.0;
+        assert_eq!(msl_window, app.window_y[1]);
+        app.next_altitude_ref();
+        let agl_window = ((last.1 * 1000.0 - app.altitude_offset()) / 1000.0).round() + 5.0;
+        assert_eq!(agl_window, app.window_y[1]);
+        assert_ne!(hae_window, agl_window);
+    }
+
+    #[test]
+    fn grid_position() {
+        let mut app = test_app();
+        assert_eq!(None, app.position_text());
+        app.next_chunk();
+
+        assert!(app.position_text().unwrap().starts_with("31."));
+        app.next_grid_format();
+        let utm = app.position_text().unwrap();
+        assert!(utm.starts_with("13R ") && utm.ends_with('N'));
+        app.next_grid_format();
+        let mgrs = app.position_text().unwrap();
+        assert!(mgrs.starts_with("13R "));
+        assert_eq!(18, mgrs.len());
+    }
+
+    #[test]
+    fn table_view() {
+        let mut app = test_app();
+        let mut terminal = Terminal::new(TestBackend::new(200, 40)).unwrap();
+        app.chunks[0].records[0].flags = QualityFlags::BELOW_SURFACE;
+        app.next_chunk();
+        app.handle_key_event(KeyCode::Char('t').into());
+        assert_eq!(Tab::Table, app.tab);
+
+        let records = app.current_records().len();
+        for _ in 0..records + 1 {
+            app.handle_key_event(KeyCode::Down.into());
+        }
+        assert_eq!(records - 1, app.table_row);
+
+        // Hide timestamp_ns
+        app.handle_key_event(KeyCode::Right.into());
+        app.handle_key_event(KeyCode::Enter.into());
+        assert_eq!(vec![1], app.hidden_fields);
+        let values = app.table_values(&app.current_records()[0]);
+        assert_eq!(TABLE_FIELDS.len(), values.len());
+        terminal.draw(|frame| app.draw(frame)).unwrap();
+        // Only the flagged first record is magenta
+        let buffer = terminal.backend().buffer();
+        let magenta_rows: Vec<u16> = (0..buffer.area.height)
+            .filter(|y| (0..buffer.area.width).any(|x| buffer[(x, *y)].fg == Color::Magenta))
+            .collect();
+        assert_eq!(1, magenta_rows.len());
+        assert!(screen(&terminal)
+            .lines()
+            .nth(usize::from(magenta_rows[0]))
+            .unwrap()
+            .contains("│0.000 "));
+
+        // The cursor stays within shorter chunks, down to the empty bins of a gap
+        app.table_row = 1_000;
+        app.next_chunk();
+        let last = app.current_records().len().saturating_sub(1);
+        assert_eq!(last, app.table_row);
+
+        app.handle_key_event(KeyCode::Enter.into());
+        assert!(app.hidden_fields.is_empty());
+        app.handle_key_event(KeyCode::Char('t').into());
+        assert_eq!(Tab::Overview, app.tab);
+    }
+
+    #[test]
+    fn custom_layout() {
+        let layout = DashboardLayout::parse(
+            r#"
+            [[rows]]
+            size = "30%"
+            widgets = [{ widget = "readouts" }, { widget = "map", size = "40%" }]
+
+            [[rows]]
+            size = "fill"
+            widgets = [{ widget = "telemetry" }, { widget = "events" }]
+            "#,
+        )
+        .unwrap();
+        let mut app = test_app().with_layout(layout);
+        let mut terminal = Terminal::new(TestBackend::new(120, 40)).unwrap();
+
+        app.next_chunk();
+        terminal.draw(|frame| app.draw(frame)).unwrap();
+
+        let screen: String = terminal
+            .backend()
+            .buffer()
+            .content()
+            .iter()
+            .map(|cell| cell.symbol())
+            .collect();
+        assert!(screen.contains("Ground track from the pad"));
+        assert!(screen.contains("Events"));
+        assert!(!screen.contains("Percent through mission"));
+    }
+
+    #[test]
+    fn switch_tabs() {
+        let records = read_csv("data/test.csv").unwrap();
+        let events = vec![FlightEvent {
+            kind: crate::events::EventKind::Liftoff,
+            index: 2,
+            timestamp_ns: records[2].timestamp_ns,
+        }];
+        let predictions = vec![predict(&records[0], 0.0, None, &Ellipsoid::WGS84)];
+        let mut app = test_app()
+            .with_flight_events(events)
+            .with_predictions(predictions, None);
+        let mut terminal = Terminal::new(TestBackend::new(120, 40)).unwrap();
+        app.next_chunk();
+        app.next_chunk();
+
+        for (key, tab) in ['1', '2', '3', '4', '5'].into_iter().zip(Tab::ALL) {
+            app.handle_key_event(KeyCode::Char(key).into());
+            assert_eq!(tab, app.tab);
+            terminal.draw(|frame| app.draw(frame)).unwrap();
+        }
+        app.handle_key_event(KeyCode::Tab.into());
+        assert_eq!(Tab::Overview, app.tab);
+        app.handle_key_event(KeyCode::BackTab.into());
+        assert_eq!(Tab::Events, app.tab);
+
+        // The track starts at the pad
+        assert!(!app.track_points.is_empty());
+        let (east, north) = app.track_points[0];
+        assert!(east.abs() < 1e-6 && north.abs() < 1e-6);
+    }
 }